@@ -0,0 +1,246 @@
+use cosmwasm_std::{Order, Pair, Storage};
+use std::{cmp::Ordering, collections::BTreeMap, iter, iter::Peekable, ops::Bound};
+
+/// Pending write of a `StorageBuffer`.
+struct Write {
+    value: Option<Vec<u8>>,
+    /// The key was removed before being set again, its stored value might be unchanged.
+    removed: bool,
+}
+
+/// Read-through buffer staging the writes of a replace, so nothing is written when an index fails
+/// and only the keys whose value changed are written to the underlying storage.
+pub(crate) struct StorageBuffer<'a> {
+    store: &'a dyn Storage,
+    writes: BTreeMap<Vec<u8>, Write>,
+}
+
+impl<'a> StorageBuffer<'a> {
+    pub(crate) fn new(store: &'a dyn Storage) -> Self {
+        Self {
+            store,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn into_writes(self) -> PendingWrites {
+        PendingWrites(self.writes)
+    }
+}
+
+/// Writes collected by a `StorageBuffer`.
+pub(crate) struct PendingWrites(BTreeMap<Vec<u8>, Write>);
+
+impl PendingWrites {
+    /// Apply the writes to `store`, skipping keys removed then set to the value they already
+    /// had.
+    pub(crate) fn flush(self, store: &mut dyn Storage) {
+        for (key, write) in self.0 {
+            match write.value {
+                Some(value) => {
+                    if !write.removed || store.get(&key).as_ref() != Some(&value) {
+                        store.set(&key, &value);
+                    }
+                }
+                None => store.remove(&key),
+            }
+        }
+    }
+}
+
+impl Storage for StorageBuffer<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(write) => write.value.clone(),
+            None => self.store.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Pair> + 'b> {
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Box::new(iter::empty());
+            }
+        }
+
+        let bounds = (
+            start.map_or(Bound::Unbounded, Bound::Included),
+            end.map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        let writes = self.writes.range::<[u8], _>(bounds);
+        let writes: PendingRange = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+
+        Box::new(MergedRange {
+            store: self.store.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        let removed = matches!(self.writes.get(key), Some(w) if w.removed);
+        self.writes.insert(
+            key.to_vec(),
+            Write {
+                value: Some(value.to_vec()),
+                removed,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(
+            key.to_vec(),
+            Write {
+                value: None,
+                removed: true,
+            },
+        );
+    }
+}
+
+/// Pending writes of a `StorageBuffer` in range order.
+type PendingRange<'b> = Box<dyn Iterator<Item = (&'b Vec<u8>, &'b Write)> + 'b>;
+
+/// Range of a `StorageBuffer`, merging the underlying range with the pending writes as it goes
+/// instead of loading the whole range first.
+struct MergedRange<'b> {
+    store: Peekable<Box<dyn Iterator<Item = Pair> + 'b>>,
+    writes: Peekable<PendingRange<'b>>,
+    order: Order,
+}
+
+impl Iterator for MergedRange<'_> {
+    type Item = Pair;
+
+    fn next(&mut self) -> Option<Pair> {
+        loop {
+            let ordering = match (self.store.peek(), self.writes.peek()) {
+                (_, None) => return self.store.next(),
+                (None, Some(_)) => Ordering::Greater,
+                (Some((stored, _)), Some((written, _))) => match self.order {
+                    Order::Ascending => stored.cmp(*written),
+                    Order::Descending => (*written).cmp(stored),
+                },
+            };
+
+            match ordering {
+                Ordering::Less => return self.store.next(),
+                Ordering::Equal => {
+                    self.store.next();
+                }
+                Ordering::Greater => {}
+            }
+            if let Some((
+                key,
+                Write {
+                    value: Some(value), ..
+                },
+            )) = self.writes.next()
+            {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn buffer_reads_through() {
+        let mut store = MockStorage::new();
+        store.set(b"a", b"1");
+        store.set(b"b", b"2");
+        store.set(b"c", b"3");
+
+        let mut buffer = StorageBuffer::new(&store);
+        buffer.remove(b"a");
+        buffer.set(b"b", b"2");
+        buffer.remove(b"c");
+        buffer.set(b"c", b"4");
+        buffer.set(b"d", b"5");
+
+        assert_eq!(buffer.get(b"a"), None);
+        assert_eq!(buffer.get(b"c"), Some(b"4".to_vec()));
+        assert_eq!(
+            buffer
+                .range(Some(b"b"), Some(b"d"), Order::Descending)
+                .collect::<Vec<_>>(),
+            vec![
+                (b"c".to_vec(), b"4".to_vec()),
+                (b"b".to_vec(), b"2".to_vec())
+            ]
+        );
+
+        buffer.into_writes().flush(&mut store);
+        assert_eq!(
+            store
+                .range(None, None, Order::Ascending)
+                .collect::<Vec<_>>(),
+            vec![
+                (b"b".to_vec(), b"2".to_vec()),
+                (b"c".to_vec(), b"4".to_vec()),
+                (b"d".to_vec(), b"5".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_range_merges_writes() {
+        let mut store = MockStorage::new();
+        for key in [b"b", b"d", b"f"] {
+            store.set(key, b"0");
+        }
+
+        let mut buffer = StorageBuffer::new(&store);
+        buffer.set(b"a", b"1");
+        buffer.remove(b"b");
+        buffer.set(b"d", b"1");
+        buffer.set(b"e", b"1");
+        buffer.remove(b"g");
+        buffer.set(b"h", b"1");
+
+        let keys = |order| {
+            buffer
+                .range(None, None, order)
+                .map(|(k, v)| (String::from_utf8(k).unwrap(), v))
+                .collect::<Vec<_>>()
+        };
+        let ascending = vec![
+            ("a".to_string(), b"1".to_vec()),
+            ("d".to_string(), b"1".to_vec()),
+            ("e".to_string(), b"1".to_vec()),
+            ("f".to_string(), b"0".to_vec()),
+            ("h".to_string(), b"1".to_vec()),
+        ];
+        assert_eq!(keys(Order::Ascending), ascending);
+        assert_eq!(
+            keys(Order::Descending),
+            ascending.into_iter().rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            buffer
+                .range(Some(b"c"), Some(b"f"), Order::Ascending)
+                .map(|(k, _)| k)
+                .collect::<Vec<_>>(),
+            vec![b"d".to_vec(), b"e".to_vec()]
+        );
+        assert_eq!(
+            buffer
+                .range(Some(b"f"), Some(b"c"), Order::Ascending)
+                .count(),
+            0
+        );
+    }
+}
@@ -3,7 +3,9 @@ use cw_storage_plus::{Index, Map, Prefix, Prefixer, PrimaryKey};
 use serde::{de::DeserializeOwned, Serialize};
 use std::borrow::Cow;
 
-use super::{helpers::deserialize_multi_kv, DeserializeFn};
+use super::{
+    cursor::CursorPrefix, helpers::deserialize_multi_kv, verify::VerifyIndex, DeserializeFn,
+};
 
 #[derive(Clone)]
 pub struct ConditionalMultiIndex<'a, K, T> {
@@ -78,6 +80,20 @@ where
     }
 }
 
+impl<'a, K, T> VerifyIndex<T> for ConditionalMultiIndex<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: for<'key> PrimaryKey<'key>,
+{
+    fn idx_namespace(&self) -> &str {
+        &self.idx_namespace
+    }
+
+    fn entry_key(&self, pk: &[u8], data: &T) -> Option<Vec<u8>> {
+        (self.cond_fn)(data).then(|| (self.idx_fn)(data, pk.to_vec()).joined_key())
+    }
+}
+
 impl<'a, K, T> ConditionalMultiIndex<'a, K, T>
 where
     T: Serialize + DeserializeOwned + Clone,
@@ -114,15 +130,41 @@ where
     pub fn index_key(&self, k: K) -> Vec<u8> {
         k.joined_key()
     }
+
+    /// Like `prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_prefix(&self, p: <K as PrimaryKey<'_>>::Prefix) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.prefix(p),
+            self.pk_namespace.as_bytes(),
+            match self.dese_fn {
+                Some(f) => f,
+                None => deserialize_multi_kv,
+            },
+        )
+    }
+
+    /// Like `sub_prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_sub_prefix(&self, p: <K as PrimaryKey<'_>>::SubPrefix) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.sub_prefix(p),
+            self.pk_namespace.as_bytes(),
+            match self.dese_fn {
+                Some(f) => f,
+                None => deserialize_multi_kv,
+            },
+        )
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use cosmwasm_std::{testing::MockStorage, Uint128};
-    use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex, PrimaryKey, U128Key, U64Key};
+    use cosmwasm_std::{testing::MockStorage, Binary, Uint128};
+    use cw_storage_plus::{
+        Index, IndexList, IndexedMap, Map, MultiIndex, PrimaryKey, U128Key, U64Key,
+    };
     use serde::{Deserialize, Serialize};
 
-    use crate::cow::deserialize_multi_kv_custom_pk;
+    use crate::cow::{deserialize_multi_kv_custom_pk, IndexReport, IndexedMapCow, VerifyIndex};
 
     use super::ConditionalMultiIndex;
 
@@ -161,7 +203,7 @@ mod test {
                     |t, _| {
                         (
                             t.val.u128().into(),
-                            U64Key::new(u64::max_value() - t.id).joined_key(),
+                            U64Key::new(u64::MAX - t.id).joined_key(),
                         )
                     },
                     // only add to val if t.val > 100
@@ -169,7 +211,7 @@ mod test {
                     Some(|s, pk, kv| {
                         deserialize_multi_kv_custom_pk(s, pk, kv, |old_kv| {
                             U64Key::new(
-                                u64::max_value()
+                                u64::MAX
                                     - u64::from_be_bytes(old_kv.as_slice().try_into().unwrap()),
                             )
                             .joined_key()
@@ -304,4 +346,42 @@ mod test {
 
         assert_eq!(v_n, vec![(2, 101), (0, 101), (1, 100),]);
     }
+
+    #[test]
+    fn verify_skips_unmatched() {
+        let mut storage = MockStorage::new();
+        let idm = IndexedMapCow::<U64Key, _, _>::new_ref("test", idm().idx);
+        let primary: Map<U64Key, Test> = Map::new("test");
+
+        for (id, val) in [(0u64, 101u64), (1, 100)] {
+            let data = Test {
+                id,
+                val: Uint128::from(val),
+            };
+            idm.save(&mut storage, id.into(), &data).unwrap();
+        }
+
+        let indexes: [&dyn VerifyIndex<Test>; 2] = [&idm.idx().val, &idm.idx().val_inv];
+        assert!(idm
+            .verify(&storage, &indexes)
+            .unwrap()
+            .iter()
+            .all(IndexReport::is_consistent));
+
+        let data = Test {
+            id: 1,
+            val: Uint128::from(150u64),
+        };
+        primary.save(&mut storage, 1.into(), &data).unwrap();
+
+        let reports = idm.verify(&storage, &indexes).unwrap();
+        assert_eq!(
+            reports[0].missing,
+            vec![Binary(1u64.to_be_bytes().to_vec())]
+        );
+        assert_eq!(
+            reports[1].missing,
+            vec![Binary(1u64.to_be_bytes().to_vec())]
+        );
+    }
 }
@@ -0,0 +1,102 @@
+use cosmwasm_std::{Binary, Order, Pair, StdResult, Storage};
+use cw_storage_plus::{range_with_prefix, Bound, Prefix};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+
+use super::helpers::DeserializeFn;
+
+/// Opaque position in an index range, serialized as base64.
+///
+/// Holds the raw index key after the range prefix, so resuming from it does not depend on how
+/// the pk was encoded into the index key or remapped by `dese_fn`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cursor(pub(crate) Binary);
+
+impl Cursor {
+    pub fn from_base64(encoded: &str) -> StdResult<Self> {
+        Binary::from_base64(encoded).map(Self)
+    }
+
+    pub fn to_base64(&self) -> String {
+        self.0.to_base64()
+    }
+
+    /// Bound starting right after this cursor.
+    pub fn exclusive(&self) -> Bound {
+        Bound::exclusive(self.0.to_vec())
+    }
+
+    /// Bound starting at this cursor.
+    pub fn inclusive(&self) -> Bound {
+        Bound::inclusive(self.0.to_vec())
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_base64())
+    }
+}
+
+/// Prefix of a multi index that keeps the raw index key of every item as a `Cursor`.
+pub struct CursorPrefix<T> {
+    storage_prefix: Vec<u8>,
+    pk_namespace: Vec<u8>,
+    de_fn: DeserializeFn<T>,
+}
+
+impl<T> CursorPrefix<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub(crate) fn new(prefix: Prefix<T>, pk_namespace: &[u8], de_fn: DeserializeFn<T>) -> Self {
+        Self {
+            storage_prefix: prefix.to_vec(),
+            pk_namespace: pk_namespace.to_vec(),
+            de_fn,
+        }
+    }
+
+    pub fn range<'a>(
+        &self,
+        store: &'a dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(Cursor, Pair<T>)>> + 'a>
+    where
+        T: 'a,
+    {
+        let de_fn = self.de_fn;
+        let pk_name = self.pk_namespace.clone();
+        let mapped =
+            range_with_prefix(store, &self.storage_prefix, min, max, order).map(move |(k, v)| {
+                let cursor = Cursor(Binary(k.clone()));
+                (de_fn)(store, &pk_name, (k, v)).map(|pair| (cursor, pair))
+            });
+        Box::new(mapped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cursor_base64() {
+        let cursor = Cursor(Binary(vec![0, 1, 255]));
+        assert_eq!(cursor.to_base64(), "AAH/");
+        assert_eq!(cursor.to_string(), "AAH/");
+        assert_eq!(Cursor::from_base64("AAH/").unwrap(), cursor);
+        assert!(Cursor::from_base64("!").is_err());
+
+        assert_eq!(
+            cosmwasm_std::to_vec(&cursor).unwrap(),
+            br#""AAH/""#.to_vec()
+        );
+        assert_eq!(
+            cosmwasm_std::from_slice::<Cursor>(br#""AAH/""#).unwrap(),
+            cursor
+        );
+    }
+}
@@ -3,7 +3,11 @@ use cw_storage_plus::{Index, Map, Prefix, Prefixer, PrimaryKey};
 use serde::{de::DeserializeOwned, Serialize};
 use std::borrow::Cow;
 
-use super::helpers::{deserialize_multi_kv, DeserializeFn};
+use super::{
+    cursor::CursorPrefix,
+    helpers::{deserialize_multi_kv, DeserializeFn},
+    verify::VerifyIndex,
+};
 
 #[derive(Clone)]
 pub struct CustomDeseMultiIndex<'a, K, T> {
@@ -60,6 +64,20 @@ where
     }
 }
 
+impl<'a, K, T> VerifyIndex<T> for CustomDeseMultiIndex<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: for<'key> PrimaryKey<'key>,
+{
+    fn idx_namespace(&self) -> &str {
+        &self.idx_namespace
+    }
+
+    fn entry_key(&self, pk: &[u8], data: &T) -> Option<Vec<u8>> {
+        Some((self.idx_fn)(data, pk.to_vec()).joined_key())
+    }
+}
+
 impl<'a, K, T> CustomDeseMultiIndex<'a, K, T>
 where
     T: Serialize + DeserializeOwned + Clone,
@@ -96,6 +114,30 @@ where
     pub fn index_key(&self, k: K) -> Vec<u8> {
         k.joined_key()
     }
+
+    /// Like `prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_prefix(&self, p: <K as PrimaryKey<'_>>::Prefix) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.prefix(p),
+            self.pk_namespace.as_bytes(),
+            match self.dese_fn {
+                Some(f) => f,
+                None => deserialize_multi_kv,
+            },
+        )
+    }
+
+    /// Like `sub_prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_sub_prefix(&self, p: <K as PrimaryKey<'_>>::SubPrefix) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.sub_prefix(p),
+            self.pk_namespace.as_bytes(),
+            match self.dese_fn {
+                Some(f) => f,
+                None => deserialize_multi_kv,
+            },
+        )
+    }
 }
 
 #[cfg(test)]
@@ -104,7 +146,7 @@ mod test {
     use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex, PrimaryKey, U128Key, U64Key};
     use serde::{Deserialize, Serialize};
 
-    use crate::cow::deserialize_multi_kv_custom_pk;
+    use crate::cow::{deserialize_multi_kv_custom_pk, deserialize_multi_kv_desc_pk, Desc};
 
     use super::CustomDeseMultiIndex;
 
@@ -117,11 +159,12 @@ mod test {
     struct TestIndexes<'a> {
         val: CustomDeseMultiIndex<'a, (U128Key, Vec<u8>), Test>,
         val_n: MultiIndex<'a, (U128Key, Vec<u8>), Test>,
+        val_desc: CustomDeseMultiIndex<'a, (Desc<Uint128>, Vec<u8>), Test>,
     }
 
     impl IndexList<Test> for TestIndexes<'_> {
         fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Test>> + '_> {
-            let v: Vec<&dyn Index<Test>> = vec![&self.val, &self.val_n];
+            let v: Vec<&dyn Index<Test>> = vec![&self.val, &self.val_n, &self.val_desc];
             Box::new(v.into_iter())
         }
     }
@@ -134,13 +177,13 @@ mod test {
                     |t, _| {
                         (
                             t.val.u128().into(),
-                            U64Key::new(u64::max_value() - t.id).joined_key(),
+                            U64Key::new(u64::MAX - t.id).joined_key(),
                         )
                     },
                     Some(|s, pk, kv| {
                         deserialize_multi_kv_custom_pk(s, pk, kv, |old_kv| {
                             U64Key::new(
-                                u64::max_value()
+                                u64::MAX
                                     - u64::from_be_bytes(old_kv.as_slice().try_into().unwrap()),
                             )
                             .joined_key()
@@ -150,6 +193,12 @@ mod test {
                     "test__val",
                 ),
                 val_n: MultiIndex::new(|t, k| (t.val.u128().into(), k), "test", "test__val_n"),
+                val_desc: CustomDeseMultiIndex::new_ref(
+                    |t, _| (Desc::new(t.val), Desc::new(t.id).joined_key()),
+                    Some(deserialize_multi_kv_desc_pk::<u64, Test>),
+                    "test",
+                    "test__val_desc",
+                ),
             },
         )
     }
@@ -258,4 +307,114 @@ mod test {
         // val: Descending, id: Descending
         assert_eq!(vn, vec![2, 3, 1, 0]);
     }
+
+    #[test]
+    fn desc_key_with_desc_pk() {
+        let mut storage = MockStorage::new();
+        for (id, val) in [(0u64, 100u64), (1, 100), (2, 200), (3, 100)] {
+            idm()
+                .save(
+                    &mut storage,
+                    id.into(),
+                    &Test {
+                        id,
+                        val: Uint128::from(val),
+                    },
+                )
+                .unwrap();
+        }
+
+        let v = idm()
+            .idx
+            .val_desc
+            .sub_prefix(())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>();
+
+        // val: Descending, id: Descending
+        assert_eq!(
+            v.iter().map(|(_, t)| t.id).collect::<Vec<_>>(),
+            vec![2, 3, 1, 0]
+        );
+        // pk is recovered as the primary map key
+        assert_eq!(v[0].0, U64Key::new(2).joined_key());
+
+        let v = idm()
+            .idx
+            .val_desc
+            .prefix(Desc::new(Uint128::from(100u64)))
+            .range(&storage, None, None, Order::Descending)
+            .map(|e| e.unwrap().1.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(v, vec![0, 1, 3]);
+    }
+
+    struct NameIndexes<'a> {
+        val: CustomDeseMultiIndex<'a, (Desc<Uint128>, Vec<u8>), Test>,
+    }
+
+    impl IndexList<Test> for NameIndexes<'_> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Test>> + '_> {
+            let v: Vec<&dyn Index<Test>> = vec![&self.val];
+            Box::new(v.into_iter())
+        }
+    }
+
+    #[test]
+    fn desc_string_pk() {
+        let mut storage = MockStorage::new();
+        let idm: IndexedMap<&str, Test, NameIndexes> = IndexedMap::new(
+            "names",
+            NameIndexes {
+                val: CustomDeseMultiIndex::new_ref(
+                    |t, pk| {
+                        let name = String::from_utf8(pk).unwrap();
+                        (Desc::new(t.val), Desc::new(name).joined_key())
+                    },
+                    Some(deserialize_multi_kv_desc_pk::<String, Test>),
+                    "names",
+                    "names__val",
+                ),
+            },
+        );
+
+        let names = ["a", "a\0b", "bb", "\0", "é"];
+        for (id, name) in names.iter().enumerate() {
+            let val = Uint128::from(id as u64 % 2);
+            idm.save(&mut storage, name, &Test { id: id as u64, val })
+                .unwrap();
+        }
+
+        let v = idm
+            .idx
+            .val
+            .sub_prefix(())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap())
+            .map(|(pk, t)| (String::from_utf8(pk).unwrap(), t.id))
+            .collect::<Vec<_>>();
+
+        // val: Descending, name: Descending
+        assert_eq!(
+            v,
+            vec![
+                ("a\0b".to_string(), 1),
+                ("\0".to_string(), 3),
+                ("é".to_string(), 4),
+                ("bb".to_string(), 2),
+                ("a".to_string(), 0),
+            ]
+        );
+
+        let v = idm
+            .idx
+            .val
+            .prefix(Desc::new(Uint128::from(1u64)))
+            .range(&storage, None, None, Order::Descending)
+            .map(|e| e.unwrap().1.id)
+            .collect::<Vec<_>>();
+        assert_eq!(v, vec![3, 1]);
+    }
 }
@@ -0,0 +1,218 @@
+/// Deserialize raw storage key back into its typed representation, used by `range` and `keys`
+/// of the cow accessors to return typed keys.
+///
+/// Modified from:
+/// https://github.com/CosmWasm/cw-plus/blob/v0.13.4/packages/storage-plus/src/de.rs
+use cosmwasm_std::{Addr, StdError, StdResult};
+use cw_storage_plus::{
+    Endian, I128Key, I16Key, I32Key, I64Key, I8Key, U128Key, U16Key, U32Key, U64Key, U8Key,
+};
+
+pub trait KeyDeserialize {
+    type Output: Sized;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output>;
+
+    fn from_slice(value: &[u8]) -> StdResult<Self::Output> {
+        Self::from_vec(value.to_vec())
+    }
+}
+
+impl KeyDeserialize for () {
+    type Output = ();
+
+    #[inline(always)]
+    fn from_vec(_value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(())
+    }
+}
+
+impl KeyDeserialize for Vec<u8> {
+    type Output = Vec<u8>;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(value)
+    }
+}
+
+impl KeyDeserialize for &[u8] {
+    type Output = Vec<u8>;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(value)
+    }
+}
+
+impl KeyDeserialize for String {
+    type Output = String;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        String::from_utf8(value).map_err(StdError::invalid_utf8)
+    }
+}
+
+impl KeyDeserialize for &str {
+    type Output = String;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Self::Output::from_vec(value)
+    }
+}
+
+impl KeyDeserialize for Addr {
+    type Output = Addr;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(Addr::unchecked(String::from_vec(value)?))
+    }
+}
+
+impl KeyDeserialize for &Addr {
+    type Output = Addr;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Self::Output::from_vec(value)
+    }
+}
+
+macro_rules! int_key_de {
+    (for $($k:ty => $t:ty),+) => {
+        $(impl KeyDeserialize for $k {
+            type Output = $t;
+
+            #[inline(always)]
+            fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+                let mut buf = <$t as Endian>::Buf::default();
+                if buf.len() != value.len() {
+                    return Err(StdError::generic_err(format!(
+                        "Invalid integer key length: expected {}, got {}",
+                        buf.len(),
+                        value.len()
+                    )));
+                }
+                buf.copy_from_slice(&value);
+
+                Ok(<$t as Endian>::from_be_bytes(buf))
+            }
+        })*
+    }
+}
+
+int_key_de!(for
+    U8Key => u8, U16Key => u16, U32Key => u32, U64Key => u64, U128Key => u128,
+    I8Key => i8, I16Key => i16, I32Key => i32, I64Key => i64, I128Key => i128
+);
+
+fn parse_length(value: &[u8]) -> StdResult<usize> {
+    Ok(u16::from_be_bytes(
+        value
+            .try_into()
+            .map_err(|_| StdError::generic_err("Could not read 2 byte length"))?,
+    )
+    .into())
+}
+
+/// Split the first length-prefixed element from a joined key, returning it and the remaining bytes.
+pub(crate) fn split_first_key(mut value: Vec<u8>) -> StdResult<(Vec<u8>, Vec<u8>)> {
+    if value.len() < 2 {
+        return Err(StdError::generic_err("Could not read 2 byte length"));
+    }
+    let mut rest = value.split_off(2);
+    let len = parse_length(&value)?;
+    if rest.len() < len {
+        return Err(StdError::generic_err(
+            "Key is shorter than its length prefix",
+        ));
+    }
+    let tail = rest.split_off(len);
+
+    Ok((rest, tail))
+}
+
+impl<T: KeyDeserialize, U: KeyDeserialize> KeyDeserialize for (T, U) {
+    type Output = (T::Output, U::Output);
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        let (t, u) = split_first_key(value)?;
+
+        Ok((T::from_vec(t)?, U::from_vec(u)?))
+    }
+}
+
+impl<T: KeyDeserialize, U: KeyDeserialize, V: KeyDeserialize> KeyDeserialize for (T, U, V) {
+    type Output = (T::Output, U::Output, V::Output);
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        let (t, uv) = split_first_key(value)?;
+        let (u, v) = split_first_key(uv)?;
+
+        Ok((T::from_vec(t)?, U::from_vec(u)?, V::from_vec(v)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cw_storage_plus::PrimaryKey;
+
+    const BYTES: &[u8] = b"Hello";
+    const STRING: &str = "Hello";
+
+    #[test]
+    fn deserialize_bytes_works() {
+        assert_eq!(<Vec<u8>>::from_slice(BYTES).unwrap(), BYTES);
+        assert_eq!(<&[u8]>::from_slice(BYTES).unwrap(), BYTES);
+    }
+
+    #[test]
+    fn deserialize_string_works() {
+        assert_eq!(<String>::from_slice(BYTES).unwrap(), STRING);
+        assert_eq!(<&str>::from_slice(BYTES).unwrap(), STRING);
+        assert!(matches!(
+            <String>::from_slice(b"\xc3").err(),
+            Some(StdError::InvalidUtf8 { .. })
+        ));
+    }
+
+    #[test]
+    fn deserialize_addr_works() {
+        assert_eq!(<Addr>::from_slice(BYTES).unwrap(), Addr::unchecked(STRING));
+        assert_eq!(<&Addr>::from_slice(BYTES).unwrap(), Addr::unchecked(STRING));
+    }
+
+    #[test]
+    fn deserialize_int_key_works() {
+        assert_eq!(U8Key::from_slice(&[1]).unwrap(), 1u8);
+        assert_eq!(U32Key::from_slice(&[1, 0, 0, 0]).unwrap(), 16777216u32);
+        assert_eq!(
+            U64Key::from_slice(&U64Key::new(1234).joined_key()).unwrap(),
+            1234u64
+        );
+        assert!(U64Key::from_slice(&[1, 0]).is_err());
+    }
+
+    #[test]
+    fn deserialize_tuple_works() {
+        assert_eq!(
+            <(&[u8], &str)>::from_slice(&(BYTES, STRING).joined_key()).unwrap(),
+            (BYTES.to_vec(), STRING.to_string())
+        );
+        assert_eq!(
+            <(U64Key, &str, Vec<u8>)>::from_slice(
+                &(U64Key::new(8), STRING, BYTES.to_vec()).joined_key()
+            )
+            .unwrap(),
+            (8u64, STRING.to_string(), BYTES.to_vec())
+        );
+        assert!(<(&[u8], &str)>::from_slice(b"\x00").is_err());
+        assert!(<(&[u8], &str)>::from_slice(b"\x00\x09ab").is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use cosmwasm_std::{Addr, Pair, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::{Prefixer, PrimaryKey};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+use super::{
+    de::KeyDeserialize,
+    helpers::{deserialize_multi_kv_with, pk_tail},
+};
+
+/// Key type usable in `Desc`, convertible from and to its ascending raw key.
+pub trait DescKey: Sized {
+    /// Length of every raw key if it is fixed, in which case inverting the bytes is enough to
+    /// reverse the ordering. `None` for variable width keys, whose raw keys must not contain
+    /// `0xFF` bytes, like UTF-8 `String` and `Addr`, so a descending pk can be read back from
+    /// the end of an index key.
+    const WIDTH: Option<usize>;
+
+    /// Raw key, the same bytes the type is stored with as an ascending key.
+    fn to_asc(&self) -> Vec<u8>;
+
+    fn from_asc(raw: Vec<u8>) -> StdResult<Self>;
+}
+
+macro_rules! integer_desc {
+    (for $($t:ty),+) => {
+        $(impl DescKey for $t {
+            const WIDTH: Option<usize> = Some(std::mem::size_of::<$t>());
+
+            fn to_asc(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
+
+            fn from_asc(raw: Vec<u8>) -> StdResult<Self> {
+                Ok(<$t>::from_be_bytes(raw.as_slice().try_into().map_err(|_| {
+                    StdError::generic_err(format!(
+                        "Invalid descending key length {} for {}",
+                        raw.len(),
+                        stringify!($t)
+                    ))
+                })?))
+            }
+        })*
+    }
+}
+
+integer_desc!(for u8, u16, u32, u64, u128);
+
+impl DescKey for Uint128 {
+    const WIDTH: Option<usize> = u128::WIDTH;
+
+    fn to_asc(&self) -> Vec<u8> {
+        self.u128().to_asc()
+    }
+
+    fn from_asc(raw: Vec<u8>) -> StdResult<Self> {
+        u128::from_asc(raw).map(Uint128::new)
+    }
+}
+
+impl DescKey for String {
+    const WIDTH: Option<usize> = None;
+
+    fn to_asc(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_asc(raw: Vec<u8>) -> StdResult<Self> {
+        String::from_vec(raw)
+    }
+}
+
+impl DescKey for Addr {
+    const WIDTH: Option<usize> = None;
+
+    fn to_asc(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_asc(raw: Vec<u8>) -> StdResult<Self> {
+        Addr::from_vec(raw)
+    }
+}
+
+/// Append the order-preserving encoding of an ascending raw key to `out`, inverted if `desc`.
+///
+/// Variable width keys have `0x00` escaped as `0x00 0xFF` and are terminated by `0x00 0x00`, so
+/// a key sorts after every key it is a prefix of and can be followed by other encoded keys.
+pub(crate) fn encode_raw<K: DescKey>(asc: &[u8], desc: bool, out: &mut Vec<u8>) {
+    let start = out.len();
+    if K::WIDTH.is_some() {
+        out.extend_from_slice(asc);
+    } else {
+        for &b in asc {
+            out.push(b);
+            if b == 0x00 {
+                out.push(0xFF);
+            }
+        }
+        out.extend_from_slice(&[0x00, 0x00]);
+    }
+    if desc {
+        out[start..].iter_mut().for_each(|b| *b = !*b);
+    }
+}
+
+/// Read one key encoded by `encode_raw` from the start of `raw`, returning its ascending raw key
+/// and the number of bytes read.
+pub(crate) fn decode_raw<K: DescKey>(raw: &[u8], desc: bool) -> StdResult<(Vec<u8>, usize)> {
+    let byte = |i: usize| if desc { !raw[i] } else { raw[i] };
+    if let Some(width) = K::WIDTH {
+        if raw.len() < width {
+            return Err(StdError::generic_err(format!(
+                "Invalid key length: expected {}, got {}",
+                width,
+                raw.len()
+            )));
+        }
+        return Ok(((0..width).map(byte).collect(), width));
+    }
+
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let b = byte(i);
+        i += 1;
+        if b != 0x00 {
+            out.push(b);
+            continue;
+        }
+        match (i < raw.len()).then(|| byte(i)) {
+            Some(0xFF) => out.push(0x00),
+            Some(0x00) => return Ok((out, i + 1)),
+            _ => break,
+        }
+        i += 1;
+    }
+
+    Err(StdError::generic_err("Invalid key encoding"))
+}
+
+/// Encode an ascending raw key into its descending form.
+pub fn desc_encode<K: DescKey>(asc: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(asc.len() + 2);
+    encode_raw::<K>(asc, true, &mut out);
+    out
+}
+
+/// Decode a descending raw key from `desc_encode` back into its ascending form.
+pub fn desc_decode<K: DescKey>(desc: &[u8]) -> StdResult<Vec<u8>> {
+    let (asc, read) = decode_raw::<K>(desc, true)?;
+    if read != desc.len() {
+        return Err(StdError::generic_err("Invalid descending key encoding"));
+    }
+    Ok(asc)
+}
+
+/// Wrap a key to store it in descending order, so an ascending range returns the highest keys
+/// first. Useful for mixed ascending/descending composite keys.
+///
+/// Like `IntKey`, variable width keys are length prefixed when they are not the last element of
+/// a composite key, ordering them by length first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Desc<K> {
+    pub wrapped: Vec<u8>,
+    data: PhantomData<K>,
+}
+
+impl<K: DescKey> Desc<K> {
+    pub fn new(val: K) -> Self {
+        Self {
+            wrapped: desc_encode::<K>(&val.to_asc()),
+            data: PhantomData,
+        }
+    }
+
+    pub fn value(&self) -> StdResult<K> {
+        K::from_asc(desc_decode::<K>(&self.wrapped)?)
+    }
+}
+
+impl<K: DescKey> From<K> for Desc<K> {
+    fn from(val: K) -> Self {
+        Desc::new(val)
+    }
+}
+
+impl<'a, K: DescKey + Clone> PrimaryKey<'a> for Desc<K> {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        vec![&self.wrapped]
+    }
+}
+
+impl<'a, K: DescKey> Prefixer<'a> for Desc<K> {
+    fn prefix(&self) -> Vec<&[u8]> {
+        vec![&self.wrapped]
+    }
+}
+
+impl<K: DescKey> KeyDeserialize for Desc<K> {
+    type Output = K;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        K::from_asc(desc_decode::<K>(&value)?)
+    }
+}
+
+/// `DeserializeFn` for indexes storing their pk as `Desc<K>`, like `(val, Desc::new(id).joined_key())`,
+/// recovering the ascending pk of the primary map.
+pub fn deserialize_multi_kv_desc_pk<K: DescKey, T: DeserializeOwned>(
+    store: &dyn Storage,
+    pk_namespace: &[u8],
+    kv: Pair,
+) -> StdResult<Pair<T>> {
+    deserialize_multi_kv_with(store, pk_namespace, kv, |key, pk_len| {
+        desc_decode::<K>(desc_pk_tail::<K>(key, pk_len)?)
+    })
+}
+
+/// Descending encoding of a `pk_len` bytes pk at the end of an index key. Variable width pks are
+/// escaped and terminated, so they are read backward from their terminator, an inverted `0x00`
+/// always being the end of an escaped `0x00`.
+fn desc_pk_tail<K: DescKey>(key: &[u8], pk_len: usize) -> StdResult<&[u8]> {
+    if K::WIDTH.is_some() {
+        return pk_tail(key, pk_len);
+    }
+
+    let invalid = || StdError::generic_err("Invalid descending pk encoding");
+    let mut start = match key.len().checked_sub(2) {
+        Some(i) if key[i..] == [0xFF, 0xFF] => i,
+        _ => return Err(invalid()),
+    };
+    for _ in 0..pk_len {
+        start = start.checked_sub(1).ok_or_else(invalid)?;
+        if key[start] == 0xFF {
+            return Err(invalid());
+        }
+        if key[start] == 0x00 {
+            start = start.checked_sub(1).ok_or_else(invalid)?;
+            if key[start] != 0xFF {
+                return Err(invalid());
+            }
+        }
+    }
+    Ok(&key[start..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted_desc<K: DescKey + Clone + std::fmt::Debug + PartialEq>(values: Vec<K>) -> Vec<K> {
+        let mut keys = values
+            .into_iter()
+            .map(|v| Desc::new(v).joined_key())
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys.into_iter()
+            .map(|k| Desc::<K>::from_vec(k).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn integers_sort_descending() {
+        assert_eq!(sorted_desc(vec![1u8, 255, 0, 7]), vec![255, 7, 1, 0]);
+        assert_eq!(
+            sorted_desc(vec![1u64, u64::MAX, 0, 256]),
+            vec![u64::MAX, 256, 1, 0]
+        );
+        assert_eq!(
+            sorted_desc(vec![Uint128::new(5), Uint128::new(500), Uint128::zero()]),
+            vec![Uint128::new(500), Uint128::new(5), Uint128::zero()]
+        );
+        assert_eq!(
+            Desc::new(5u64).joined_key(),
+            (u64::MAX - 5).to_be_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn strings_sort_descending() {
+        let values = vec!["a", "ab", "", "b", "a\0", "a\0b", "ba"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let mut expected = values.clone();
+        expected.sort();
+        expected.reverse();
+
+        assert_eq!(sorted_desc(values), expected);
+        assert_eq!(
+            sorted_desc(vec![Addr::unchecked("a"), Addr::unchecked("b")]),
+            vec![Addr::unchecked("b"), Addr::unchecked("a")]
+        );
+    }
+
+    #[test]
+    fn value_roundtrip() {
+        assert_eq!(Desc::new(42u32).value().unwrap(), 42);
+        assert_eq!(
+            Desc::new("a\0b".to_string()).value().unwrap(),
+            "a\0b".to_string()
+        );
+    }
+
+    #[test]
+    fn invalid_encoding_errs() {
+        assert!(Desc::<u64>::from_vec(vec![0, 1]).is_err());
+        assert!(Desc::<String>::from_vec(vec![!b'a']).is_err());
+        assert!(Desc::<String>::from_vec(vec![!b'a', 0xFF, 0xFF, !b'b']).is_err());
+    }
+
+    #[test]
+    fn short_index_key_errs() {
+        let store = cosmwasm_std::testing::MockStorage::new();
+        let kv = (vec![0, 1], cosmwasm_std::to_vec(&8u32).unwrap());
+        let res = deserialize_multi_kv_desc_pk::<u64, u64>(&store, b"pk", kv);
+        assert!(res.unwrap_err().to_string().contains("pk length 8"));
+    }
+}
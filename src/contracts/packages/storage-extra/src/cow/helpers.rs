@@ -1,5 +1,8 @@
-use cosmwasm_std::{from_slice, Pair, StdError, StdResult, Storage};
-use serde::de::DeserializeOwned;
+use cosmwasm_std::{from_slice, Order, Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{range_with_prefix, Bound, Prefix};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::de::KeyDeserialize;
 
 pub type DeserializeFn<T> = fn(&dyn Storage, &[u8], Pair) -> StdResult<Pair<T>>;
 
@@ -9,29 +12,28 @@ pub fn deserialize_multi_kv_custom_pk<T: DeserializeOwned>(
     kv: Pair,
     pk_fn: fn(Vec<u8>) -> Vec<u8>,
 ) -> StdResult<Pair<T>> {
-    let (key, pk_len) = kv;
-
-    // Deserialize pk_len
-    let pk_len = from_slice::<u32>(pk_len.as_slice())?;
-
-    // Recover pk from last part of k
-    let offset = key.len() - pk_len as usize;
-    let pk = pk_fn(key[offset..].to_vec());
-
-    let full_key = namespaces_with_key(&[pk_namespace], pk.as_slice());
-
-    let v = store
-        .get(&full_key)
-        .ok_or_else(|| StdError::generic_err("pk not found"))?;
-    let v = from_slice::<T>(&v)?;
-
-    Ok((pk, v))
+    deserialize_multi_kv_with(store, pk_namespace, kv, |key, pk_len| {
+        Ok(pk_fn(pk_tail(key, pk_len)?.to_vec()))
+    })
 }
 
 pub(crate) fn deserialize_multi_kv<T: DeserializeOwned>(
     store: &dyn Storage,
     pk_namespace: &[u8],
     kv: Pair,
+) -> StdResult<Pair<T>> {
+    deserialize_multi_kv_with(store, pk_namespace, kv, |key, pk_len| {
+        Ok(pk_tail(key, pk_len)?.to_vec())
+    })
+}
+
+/// Load the record of a multi index entry, `pk_fn` recovering the pk of the primary map from the
+/// index key and the length of that pk.
+pub(crate) fn deserialize_multi_kv_with<T: DeserializeOwned>(
+    store: &dyn Storage,
+    pk_namespace: &[u8],
+    kv: Pair,
+    pk_fn: impl FnOnce(&[u8], usize) -> StdResult<Vec<u8>>,
 ) -> StdResult<Pair<T>> {
     let (key, pk_len) = kv;
 
@@ -39,17 +41,28 @@ pub(crate) fn deserialize_multi_kv<T: DeserializeOwned>(
     let pk_len = from_slice::<u32>(pk_len.as_slice())?;
 
     // Recover pk from last part of k
-    let offset = key.len() - pk_len as usize;
-    let pk = &key[offset..];
+    let pk = pk_fn(&key, pk_len as usize)?;
 
-    let full_key = namespaces_with_key(&[pk_namespace], pk);
+    let full_key = namespaces_with_key(&[pk_namespace], pk.as_slice());
 
     let v = store
         .get(&full_key)
         .ok_or_else(|| StdError::generic_err("pk not found"))?;
     let v = from_slice::<T>(&v)?;
 
-    Ok((pk.into(), v))
+    Ok((pk, v))
+}
+
+/// Last `len` bytes of an index key, where indexes store their pk.
+pub(crate) fn pk_tail(key: &[u8], len: usize) -> StdResult<&[u8]> {
+    let offset = key.len().checked_sub(len).ok_or_else(|| {
+        StdError::generic_err(format!(
+            "Invalid index key length: pk length {} exceeds key length {}",
+            len,
+            key.len()
+        ))
+    })?;
+    Ok(&key[offset..])
 }
 
 pub(crate) fn encode_length(namespace: &[u8]) -> [u8; 2] {
@@ -74,3 +87,63 @@ pub(crate) fn namespaces_with_key(namespaces: &[&[u8]], key: &[u8]) -> Vec<u8> {
     out.extend_from_slice(key);
     out
 }
+
+/// Delete at most `limit` entries of the index stored under `idx_namespace`, to reclaim the
+/// storage of a retired index over several transactions. Returns `true` once the namespace is
+/// empty, a `limit` of `0` deletes nothing and only checks it.
+pub fn drop_index(store: &mut dyn Storage, idx_namespace: &str, limit: usize) -> bool {
+    let prefix = namespaces_with_key(&[idx_namespace.as_bytes()], b"");
+    let keys = range_with_prefix(store, &prefix, None, None, Order::Ascending)
+        .map(|(k, _)| k)
+        .take(limit.saturating_add(1))
+        .collect::<Vec<_>>();
+
+    let done = keys.len() <= limit;
+    for key in keys.into_iter().take(limit) {
+        store.remove(&[prefix.as_slice(), &key].concat());
+    }
+    done
+}
+
+/// Range over `prefix`, deserializing each raw key through `KeyDeserialize` after prepending
+/// `key_prefix`, the joined form of the prefix the range is built from.
+pub(crate) fn range_de<'c, K, T>(
+    prefix: Prefix<T>,
+    key_prefix: Vec<u8>,
+    store: &'c dyn Storage,
+    min: Option<Bound>,
+    max: Option<Bound>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+where
+    K: KeyDeserialize,
+    K::Output: 'c,
+    T: Serialize + DeserializeOwned + 'c,
+{
+    let de_fn: fn(Vec<u8>) -> StdResult<K::Output> = K::from_vec;
+    let mapped = prefix
+        .range(store, min, max, order)
+        .map(move |kv| kv.and_then(|(k, v)| Ok((de_fn([key_prefix.as_slice(), &k].concat())?, v))));
+    Box::new(mapped)
+}
+
+/// Keys of `prefix`, deserialized through `KeyDeserialize` after prepending `key_prefix`.
+pub(crate) fn keys_de<'c, K, T>(
+    prefix: Prefix<T>,
+    key_prefix: Vec<u8>,
+    store: &'c dyn Storage,
+    min: Option<Bound>,
+    max: Option<Bound>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+where
+    K: KeyDeserialize,
+    K::Output: 'c,
+    T: Serialize + DeserializeOwned,
+{
+    let de_fn: fn(Vec<u8>) -> StdResult<K::Output> = K::from_vec;
+    let mapped = prefix
+        .keys(store, min, max, order)
+        .map(move |k| de_fn([key_prefix.as_slice(), &k].concat()));
+    Box::new(mapped)
+}
@@ -1,9 +1,18 @@
-use cosmwasm_std::{Pair, StdError, StdResult, Storage};
-use cw_storage_plus::{Index, IndexList, MultiIndex, Path, Prefix, PrimaryKey, UniqueIndex};
-use serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::{from_slice, to_vec, Binary, Order, Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{
+    Bound, Index, IndexList, Map, MultiIndex, Path, Prefix, PrimaryKey, UniqueIndex,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{borrow::Cow, marker::PhantomData};
 
-use super::indexed_map_ref::IndexedMapRef;
+use super::{
+    cursor::CursorPrefix,
+    de::KeyDeserialize,
+    helpers::{deserialize_multi_kv, keys_de},
+    indexed_map_ref::{IndexedMapRef, ReindexProgress},
+    item::ItemCow,
+    verify::{IndexReport, VerifyIndex},
+};
 
 #[derive(Debug, Clone)]
 pub struct IndexedMapCow<'a, K, T, I> {
@@ -88,6 +97,124 @@ where
     pub fn sub_prefix(&'a self, p: K::SubPrefix) -> Prefix<T> {
         self.indexed_map().sub_prefix(p)
     }
+
+    pub fn idx(&self) -> &I {
+        &self.index
+    }
+
+    pub fn range_raw<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.indexed_map().range_raw(store, min, max, order)
+    }
+
+    pub fn keys_raw<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        self.indexed_map().keys_raw(store, min, max, order)
+    }
+
+    pub fn reindex(
+        &'a self,
+        store: &mut dyn Storage,
+        index: &dyn VerifyIndex<T>,
+        progress: &ItemCow<ReindexProgress>,
+        limit: usize,
+    ) -> StdResult<ReindexProgress> {
+        self.indexed_map().reindex(store, index, progress, limit)
+    }
+
+    pub fn verify(
+        &'a self,
+        store: &dyn Storage,
+        indexes: &[&dyn VerifyIndex<T>],
+    ) -> StdResult<Vec<IndexReport>> {
+        self.indexed_map().verify(store, indexes)
+    }
+
+    pub fn repair(
+        &'a self,
+        store: &mut dyn Storage,
+        indexes: &[&dyn VerifyIndex<T>],
+    ) -> StdResult<Vec<IndexReport>> {
+        self.indexed_map().repair(store, indexes)
+    }
+}
+
+impl<'a, K, T, I> IndexedMapCow<'a, K, T, I>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    pub fn range<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        self.indexed_map().range(store, min, max, order)
+    }
+
+    pub fn keys<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        K::Output: 'c,
+    {
+        self.indexed_map().keys(store, min, max, order)
+    }
+
+    pub fn prefix_range<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        p: K::Prefix,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        self.indexed_map().prefix_range(store, p, min, max, order)
+    }
+
+    pub fn sub_prefix_range<'c>(
+        &'a self,
+        store: &'c dyn Storage,
+        p: K::SubPrefix,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        self.indexed_map()
+            .sub_prefix_range(store, p, min, max, order)
+    }
 }
 
 #[derive(Clone)]
@@ -128,7 +255,7 @@ where
     T: Serialize + DeserializeOwned + Clone,
     K: for<'key> PrimaryKey<'key>,
 {
-    pub fn multi_index(&self) -> MultiIndex<K, T> {
+    pub fn multi_index(&self) -> MultiIndex<'_, K, T> {
         MultiIndex::new(self.idx_fn, &self.pk_namespace, &self.idx_namespace)
     }
 
@@ -143,6 +270,27 @@ where
     pub fn index_key(&self, k: K) -> Vec<u8> {
         self.multi_index().index_key(k)
     }
+
+    /// Like `prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_prefix<'key>(&'key self, p: <K as PrimaryKey<'key>>::Prefix) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.prefix(p),
+            self.pk_namespace.as_bytes(),
+            deserialize_multi_kv,
+        )
+    }
+
+    /// Like `sub_prefix`, but every item is returned with its `Cursor` in the index.
+    pub fn cursor_sub_prefix<'key>(
+        &'key self,
+        p: <K as PrimaryKey<'key>>::SubPrefix,
+    ) -> CursorPrefix<T> {
+        CursorPrefix::new(
+            self.sub_prefix(p),
+            self.pk_namespace.as_bytes(),
+            deserialize_multi_kv,
+        )
+    }
 }
 
 impl<K, T> Index<T> for MultiIndexCow<'_, K, T>
@@ -159,6 +307,23 @@ where
     }
 }
 
+impl<K, T> VerifyIndex<T> for MultiIndexCow<'_, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: for<'key> PrimaryKey<'key>,
+{
+    fn idx_namespace(&self) -> &str {
+        &self.idx_namespace
+    }
+
+    fn entry_key(&self, pk: &[u8], data: &T) -> Option<Vec<u8>> {
+        Some((self.idx_fn)(data, pk.to_vec()).joined_key())
+    }
+}
+
+/// `(index key, pk, value)` returned by `UniqueIndexCow::range`.
+pub type UniqueRecord<K, T> = (K, Vec<u8>, T);
+
 #[derive(Clone)]
 pub struct UniqueIndexCow<'a, K, T> {
     pub(crate) idx_namespace: Cow<'a, str>,
@@ -186,7 +351,7 @@ where
     T: Serialize + DeserializeOwned + Clone,
     K: for<'key> PrimaryKey<'key>,
 {
-    pub fn unique_index(&self) -> UniqueIndex<K, T> {
+    pub fn unique_index(&self) -> UniqueIndex<'_, K, T> {
         UniqueIndex::new(self.idx_fn, &self.idx_namespace)
     }
 
@@ -205,6 +370,75 @@ where
     pub fn item(&self, store: &dyn Storage, idx: K) -> StdResult<Option<Pair<T>>> {
         self.unique_index().item(store, idx)
     }
+
+    /// Load the pk stored under `idx`, without deserializing the value.
+    pub fn load_by_index(&self, store: &dyn Storage, idx: K) -> StdResult<Vec<u8>> {
+        self.may_load_pk(store, idx)?
+            .ok_or_else(|| StdError::not_found("unique index entry"))
+    }
+
+    /// Load the pk stored under `idx` if present, without deserializing the value.
+    pub fn may_load_pk(&self, store: &dyn Storage, idx: K) -> StdResult<Option<Vec<u8>>> {
+        let idx_map: Map<K, UniquePk> = Map::new(&self.idx_namespace);
+        Ok(idx_map.may_load(store, idx)?.map(|r| r.pk.into()))
+    }
+
+    fn no_prefix(&self) -> Prefix<UniqueRef<T>> {
+        Prefix::new(self.idx_namespace.as_bytes(), &[])
+    }
+}
+
+impl<K, T> UniqueIndexCow<'_, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: for<'key> PrimaryKey<'key> + KeyDeserialize,
+{
+    /// Iterate over the index ordered by index key, returning `(index key, pk, value)`.
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<UniqueRecord<K::Output, T>>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        let de_fn: fn(Vec<u8>) -> StdResult<K::Output> = K::from_vec;
+        let mapped = self
+            .no_prefix()
+            .range(store, min, max, order)
+            .map(move |kv| kv.and_then(|(k, r)| Ok((de_fn(k)?, r.pk.into(), r.value))));
+        Box::new(mapped)
+    }
+
+    /// Iterate over the index keys in order.
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        K::Output: 'c,
+    {
+        keys_de::<K, UniqueRef<T>>(self.no_prefix(), vec![], store, min, max, order)
+    }
+}
+
+/// Mirror of the value stored by `UniqueIndex` in `cw-storage-plus`.
+#[derive(Serialize, Deserialize)]
+struct UniqueRef<T> {
+    pk: Binary,
+    value: T,
+}
+
+/// `UniqueRef` without its value, to read the pk only.
+#[derive(Serialize, Deserialize)]
+struct UniquePk {
+    pk: Binary,
 }
 
 impl<K, T> Index<T> for UniqueIndexCow<'_, K, T>
@@ -221,6 +455,32 @@ where
     }
 }
 
+impl<K, T> VerifyIndex<T> for UniqueIndexCow<'_, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: for<'key> PrimaryKey<'key>,
+{
+    fn idx_namespace(&self) -> &str {
+        &self.idx_namespace
+    }
+
+    fn entry_key(&self, _pk: &[u8], data: &T) -> Option<Vec<u8>> {
+        Some((self.idx_fn)(data).joined_key())
+    }
+
+    fn entry_pk(&self, value: &[u8]) -> StdResult<Option<Vec<u8>>> {
+        Ok(Some(from_slice::<UniquePk>(value)?.pk.into()))
+    }
+
+    fn entry_value(&self, pk: &[u8], data: &T) -> StdResult<Option<Vec<u8>>> {
+        let value = UniqueRef {
+            pk: Binary(pk.to_vec()),
+            value: data,
+        };
+        to_vec(&value).map(Some)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -3,14 +3,35 @@
 ///
 /// Modified from:
 /// https://github.com/CosmWasm/cw-plus/blob/v0.9.1/packages/storage-plus/src/indexed_map.rs
-use cosmwasm_std::{StdError, StdResult, Storage};
-use cw_storage_plus::{IndexList, Map, Path, Prefix, Prefixer, PrimaryKey};
-use serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::{Binary, Order, Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, IndexList, Map, Path, Prefix, Prefixer, PrimaryKey};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    buffer::StorageBuffer,
+    cursor::Cursor,
+    de::KeyDeserialize,
+    helpers::{keys_de, namespaces_with_key, range_de},
+    item::ItemCow,
+    verify::{repair_index, verify_index, IndexReport, VerifyIndex},
+};
+
+/// Progress of `reindex`, persisted between calls.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReindexProgress {
+    /// Raw pk of the last reindexed record.
+    pub cursor: Option<Cursor>,
+    /// Number of records reindexed so far.
+    pub count: u64,
+    pub done: bool,
+}
 
 pub struct IndexedMapRef<'a, K, T, I> {
     pk_namespace: &'a [u8],
     primary: Map<'a, K, T>,
-    idx: &'a I,
+    /// This is meant to be read directly to get the proper types, like:
+    /// map.idx.owner.items(...)
+    pub idx: &'a I,
 }
 
 impl<'a, K, T, I> IndexedMapRef<'a, K, T, I> {
@@ -51,19 +72,24 @@ where
         old_data: Option<&T>,
     ) -> StdResult<()> {
         let pk = key.joined_key();
-        if let Some(old) = old_data {
-            for index in self.idx.get_indexes() {
-                index.remove(store, &pk, old)?;
+        // every write is staged first, so a failing index, like a unique constraint violation,
+        // leaves the storage untouched even when the error is handled. Entries whose key and
+        // value did not change are not written again.
+        let mut buffer = StorageBuffer::new(store);
+        for index in self.idx.get_indexes() {
+            if let Some(old) = old_data {
+                index.remove(&mut buffer, &pk, old)?;
+            }
+            if let Some(updated) = data {
+                index.save(&mut buffer, &pk, updated)?;
             }
         }
         if let Some(updated) = data {
-            for index in self.idx.get_indexes() {
-                index.save(store, &pk, updated)?;
-            }
-            self.primary.save(store, key, updated)?;
+            self.primary.save(&mut buffer, key, updated)?;
         } else {
-            self.primary.remove(store, key);
+            self.primary.remove(&mut buffer, key);
         }
+        buffer.into_writes().flush(store);
         Ok(())
     }
 
@@ -95,3 +121,205 @@ where
         Prefix::new(self.pk_namespace, &p.prefix())
     }
 }
+
+impl<'a, K, T, I> IndexedMapRef<'a, K, T, I>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    fn no_prefix(&self) -> Prefix<T> {
+        Prefix::new(self.pk_namespace, &[])
+    }
+
+    /// Add the records of the map to `index`, at most `limit` per call, resuming from the
+    /// progress stored in `progress`. Used to populate an index added to a map with existing
+    /// records, `done` is set once every record has been indexed.
+    ///
+    /// Records already in `index`, like records saved through the map while the reindex is in
+    /// progress, are skipped. A record whose key is taken by another record, like a unique
+    /// constraint violation, fails the call without writing any entry of the batch nor storing
+    /// the progress. A `limit` of `0` is rejected as it would never finish.
+    pub fn reindex(
+        &self,
+        store: &mut dyn Storage,
+        index: &dyn VerifyIndex<T>,
+        progress: &ItemCow<ReindexProgress>,
+        limit: usize,
+    ) -> StdResult<ReindexProgress> {
+        if limit == 0 {
+            return Err(StdError::generic_err(
+                "reindex limit must be greater than 0",
+            ));
+        }
+
+        let mut state = progress.may_load(store)?.unwrap_or_default();
+        if state.done {
+            return Ok(state);
+        }
+
+        let min = state.cursor.as_ref().map(Cursor::exclusive);
+        let records = self
+            .range_raw(store, min, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let idx_namespace = index.idx_namespace().as_bytes();
+        state.done = records.len() < limit;
+        // entries are staged so a collision later in the batch leaves the index untouched
+        let mut buffer = StorageBuffer::new(store);
+        for (pk, data) in records {
+            if let Some(key) = index.entry_key(&pk, &data) {
+                match buffer.get(&namespaces_with_key(&[idx_namespace], &key)) {
+                    Some(value) => match index.entry_pk(&value)? {
+                        Some(other) if other != pk => {
+                            return Err(StdError::generic_err(format!(
+                                "Violates unique constraint on index {}",
+                                index.idx_namespace()
+                            )))
+                        }
+                        _ => {}
+                    },
+                    None => index.save(&mut buffer, &pk, &data)?,
+                }
+            }
+            state.count += 1;
+            state.cursor = Some(Cursor(Binary(pk)));
+        }
+
+        buffer.into_writes().flush(store);
+        progress.save(store, &state)?;
+        Ok(state)
+    }
+
+    /// Cross-check `indexes` against the records of the map, reporting for each index its
+    /// orphaned entries, records missing from it, keys shared by several records and entries
+    /// holding an outdated copy of their record.
+    ///
+    /// Loads the whole map and reads every index in one call, with no limit like `reindex`, so
+    /// the gas it uses grows with the map. Meant for queries, migrations and off-chain checks.
+    pub fn verify(
+        &self,
+        store: &dyn Storage,
+        indexes: &[&dyn VerifyIndex<T>],
+    ) -> StdResult<Vec<IndexReport>> {
+        let records = self
+            .range_raw(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        indexes
+            .iter()
+            .map(|index| verify_index(store, &records, *index))
+            .collect()
+    }
+
+    /// Like `verify`, then remove the orphaned entries, index the missing records and rewrite
+    /// the stale entries. Collisions are only reported, they need the records to be changed.
+    /// Unbounded like `verify`, it can run out of gas on large maps.
+    pub fn repair(
+        &self,
+        store: &mut dyn Storage,
+        indexes: &[&dyn VerifyIndex<T>],
+    ) -> StdResult<Vec<IndexReport>> {
+        let records = self
+            .range_raw(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut reports = vec![];
+        for index in indexes {
+            let report = verify_index(store, &records, *index)?;
+            repair_index(store, &records, *index, &report)?;
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    pub fn range_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.no_prefix().range(store, min, max, order)
+    }
+
+    pub fn keys_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        self.no_prefix().keys(store, min, max, order)
+    }
+}
+
+impl<'a, K, T, I> IndexedMapRef<'a, K, T, I>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        range_de::<K, T>(self.no_prefix(), vec![], store, min, max, order)
+    }
+
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        K::Output: 'c,
+    {
+        keys_de::<K, T>(self.no_prefix(), vec![], store, min, max, order)
+    }
+
+    /// Like `prefix(p).range(...)`, but returns the full typed key instead of the raw suffix.
+    /// Bounds are applied to the raw suffix after the prefix.
+    pub fn prefix_range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: K::Prefix,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        let key_prefix = namespaces_with_key(&p.prefix(), b"");
+        range_de::<K, T>(self.prefix(p), key_prefix, store, min, max, order)
+    }
+
+    /// Like `sub_prefix(p).range(...)`, but returns the full typed key instead of the raw suffix.
+    /// Bounds are applied to the raw suffix after the sub prefix.
+    pub fn sub_prefix_range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: K::SubPrefix,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        let key_prefix = namespaces_with_key(&p.prefix(), b"");
+        range_de::<K, T>(self.sub_prefix(p), key_prefix, store, min, max, order)
+    }
+}
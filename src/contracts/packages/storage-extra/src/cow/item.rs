@@ -32,7 +32,7 @@ impl<'a, T> ItemCow<'a, T>
 where
     T: Serialize + DeserializeOwned,
 {
-    pub fn item(&self) -> Item<T> {
+    pub fn item(&self) -> Item<'_, T> {
         Item::new(&self.namespace)
     }
 
@@ -1,8 +1,13 @@
-use cosmwasm_std::{StdError, StdResult, Storage};
-use cw_storage_plus::{Map, Prefix, PrimaryKey};
+use cosmwasm_std::{Order, Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Map, Prefix, PrimaryKey};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{borrow::Cow, marker::PhantomData};
 
+use super::{
+    de::KeyDeserialize,
+    helpers::{keys_de, range_de},
+};
+
 #[derive(Debug, Clone)]
 pub struct MapCow<'a, K, T> {
     pub(crate) namespace: Cow<'a, str>,
@@ -37,7 +42,7 @@ where
     K: PrimaryKey<'key>,
     'key: 'a,
 {
-    pub fn map(&self) -> Map<K, T> {
+    pub fn map(&self) -> Map<'_, K, T> {
         Map::new(&self.namespace)
     }
 
@@ -78,10 +83,83 @@ where
     }
 }
 
+impl<'a, K, T> MapCow<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Prefix over the whole map namespace, regardless of the key composition.
+    fn no_prefix(&self) -> Prefix<T> {
+        Prefix::new(self.namespace.as_bytes(), &[])
+    }
+
+    /// Iterate over every raw key and value in the map within the given bounds.
+    pub fn range_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<Pair<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.no_prefix().range(store, min, max, order)
+    }
+
+    /// Iterate over every raw key in the map within the given bounds.
+    pub fn keys_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        self.no_prefix().keys(store, min, max, order)
+    }
+}
+
+impl<'a, K, T> MapCow<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+    K: KeyDeserialize,
+{
+    /// Iterate over every key and value in the map within the given bounds, deserializing keys
+    /// through `KeyDeserialize`.
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'c,
+    {
+        range_de::<K, T>(self.no_prefix(), vec![], store, min, max, order)
+    }
+
+    /// Iterate over every key in the map within the given bounds, deserializing keys through
+    /// `KeyDeserialize`.
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound>,
+        max: Option<Bound>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        K::Output: 'c,
+    {
+        keys_de::<K, T>(self.no_prefix(), vec![], store, min, max, order)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use cosmwasm_std::{testing::MockStorage, Addr};
+    use cw_storage_plus::U64Key;
 
     #[test]
     fn new_owned() {
@@ -103,4 +181,79 @@ mod test {
         ADDR_REF.map().save(&mut storage, &a, &1).unwrap();
         assert_eq!(ADDR_REF.map().load(&storage, &a).unwrap(), 1);
     }
+
+    #[test]
+    fn range_and_keys() {
+        let mut storage = MockStorage::new();
+        let map: MapCow<U64Key, Addr> = MapCow::new_owned(String::from("g"));
+
+        for i in 0..4u64 {
+            map.save(&mut storage, i.into(), &Addr::unchecked(i.to_string()))
+                .unwrap();
+        }
+
+        assert_eq!(
+            map.range(&storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap(),
+            (0..4u64)
+                .map(|i| (i, Addr::unchecked(i.to_string())))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            map.keys(
+                &storage,
+                Some(Bound::exclusive_int(0u64)),
+                Some(Bound::inclusive_int(2u64)),
+                Order::Descending
+            )
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+            vec![2, 1]
+        );
+
+        assert_eq!(
+            map.keys_raw(
+                &storage,
+                None,
+                Some(Bound::exclusive_int(1u64)),
+                Order::Ascending
+            )
+            .collect::<Vec<_>>(),
+            vec![0u64.to_be_bytes().to_vec()]
+        );
+
+        assert_eq!(
+            map.range_raw(
+                &storage,
+                Some(Bound::inclusive_int(3u64)),
+                None,
+                Order::Ascending
+            )
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+            vec![(3u64.to_be_bytes().to_vec(), Addr::unchecked("3"))]
+        );
+    }
+
+    #[test]
+    fn range_composite_key() {
+        let mut storage = MockStorage::new();
+        let map: MapCow<(&Addr, U64Key), u64> = MapCow::new_owned(String::from("g"));
+
+        let a = Addr::unchecked("a");
+        let b = Addr::unchecked("b");
+
+        map.save(&mut storage, (&a, 1.into()), &1).unwrap();
+        map.save(&mut storage, (&b, 0.into()), &2).unwrap();
+        map.save(&mut storage, (&a, 0.into()), &3).unwrap();
+
+        assert_eq!(
+            map.range(&storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap(),
+            vec![((a.clone(), 0), 3), ((a, 1), 1), ((b, 0), 2)]
+        );
+    }
 }
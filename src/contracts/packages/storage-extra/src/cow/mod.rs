@@ -1,18 +1,32 @@
+mod buffer;
 mod conditional_multi_index;
+mod cursor;
 mod custom_dese_index;
-mod helpers;
+mod de;
+mod desc;
+pub(crate) mod helpers;
 mod indexed_map;
 mod indexed_map_ref;
 mod item;
 mod map;
+mod sort_key;
+mod verify;
 
 #[cfg(test)]
 mod tests;
 
 pub use conditional_multi_index::ConditionalMultiIndex;
+pub use cursor::{Cursor, CursorPrefix};
 pub use custom_dese_index::CustomDeseMultiIndex;
-pub use helpers::{deserialize_multi_kv_custom_pk, DeserializeFn};
-pub use indexed_map::{IndexedMapCow, MultiIndexCow, UniqueIndexCow};
-pub use indexed_map_ref::IndexedMapRef;
+pub use de::KeyDeserialize;
+pub use desc::{desc_decode, desc_encode, deserialize_multi_kv_desc_pk, Desc, DescKey};
+pub use helpers::{deserialize_multi_kv_custom_pk, drop_index, DeserializeFn};
+pub use indexed_map::{IndexedMapCow, MultiIndexCow, UniqueIndexCow, UniqueRecord};
+pub use indexed_map_ref::{IndexedMapRef, ReindexProgress};
 pub use item::ItemCow;
 pub use map::MapCow;
+pub use sort_key::{
+    decode_sort_field, decode_sort_pk, deserialize_multi_kv_sort_pk, encode_sort_field, SortKey,
+    Sorted,
+};
+pub use verify::{IndexReport, VerifyIndex};
@@ -0,0 +1,111 @@
+use cosmwasm_std::{Pair, StdError, StdResult, Storage};
+use cw_storage_plus::{Prefixer, PrimaryKey};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+use super::{
+    de::KeyDeserialize,
+    desc::{decode_raw, encode_raw, DescKey},
+    helpers::{deserialize_multi_kv_with, pk_tail},
+};
+
+/// Composite key encoded into a single order-preserving segment, each field ascending or
+/// descending. Usually implemented with `#[derive(SortKey)]` from `tw-storage-macros`.
+pub trait SortKey: Sized {
+    fn to_sort_key(&self) -> Vec<u8>;
+
+    fn from_sort_key(raw: &[u8]) -> StdResult<Self>;
+
+    /// Recover the ascending pk of the primary map from the last bytes of an encoded sort key,
+    /// for sort keys ending with the pk of the record.
+    fn pk_from_tail(_tail: &[u8]) -> StdResult<Vec<u8>> {
+        Err(StdError::generic_err("Sort key has no pk field"))
+    }
+}
+
+/// Append a field of a sort key to `out`, used by `#[derive(SortKey)]`.
+pub fn encode_sort_field<K: DescKey>(val: &K, desc: bool, out: &mut Vec<u8>) {
+    encode_raw::<K>(&val.to_asc(), desc, out)
+}
+
+/// Read a field of a sort key from the start of `raw` and advance past it, used by
+/// `#[derive(SortKey)]`.
+pub fn decode_sort_field<K: DescKey>(raw: &mut &[u8], desc: bool) -> StdResult<K> {
+    let (asc, read) = decode_raw::<K>(raw, desc)?;
+    *raw = &raw[read..];
+    K::from_asc(asc)
+}
+
+/// Recover the ascending raw key of a fixed width pk field from the tail of a sort key, used by
+/// `#[derive(SortKey)]`.
+pub fn decode_sort_pk<K: DescKey>(tail: &[u8], desc: bool) -> StdResult<Vec<u8>> {
+    let (asc, read) = decode_raw::<K>(tail, desc)?;
+    if read != tail.len() {
+        return Err(StdError::generic_err("Invalid sort key pk length"));
+    }
+    Ok(asc)
+}
+
+/// Wrap a `SortKey` to use it as a single segment key, keeping the ordering of its fields.
+///
+/// `PrimaryKey::key` of `cw-storage-plus` 0.9 borrows its segments from the key, so the encoded
+/// sort key has to be stored next to it and can not be implemented on the struct itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sorted<K> {
+    pub wrapped: Vec<u8>,
+    data: PhantomData<K>,
+}
+
+impl<K: SortKey> Sorted<K> {
+    pub fn new(val: &K) -> Self {
+        Self {
+            wrapped: val.to_sort_key(),
+            data: PhantomData,
+        }
+    }
+
+    pub fn value(&self) -> StdResult<K> {
+        K::from_sort_key(&self.wrapped)
+    }
+}
+
+impl<K: SortKey> From<K> for Sorted<K> {
+    fn from(val: K) -> Self {
+        Sorted::new(&val)
+    }
+}
+
+impl<'a, K: SortKey + Clone> PrimaryKey<'a> for Sorted<K> {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        vec![&self.wrapped]
+    }
+}
+
+impl<'a, K: SortKey> Prefixer<'a> for Sorted<K> {
+    fn prefix(&self) -> Vec<&[u8]> {
+        vec![&self.wrapped]
+    }
+}
+
+impl<K: SortKey> KeyDeserialize for Sorted<K> {
+    type Output = K;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        K::from_sort_key(&value)
+    }
+}
+
+/// `DeserializeFn` for indexes ending with a sort key whose last field is the pk, like
+/// `(val, key.to_sort_key())`, recovering the ascending pk of the primary map.
+pub fn deserialize_multi_kv_sort_pk<K: SortKey, T: DeserializeOwned>(
+    store: &dyn Storage,
+    pk_namespace: &[u8],
+    kv: Pair,
+) -> StdResult<Pair<T>> {
+    deserialize_multi_kv_with(store, pk_namespace, kv, |key, pk_len| {
+        K::pk_from_tail(pk_tail(key, pk_len)?)
+    })
+}
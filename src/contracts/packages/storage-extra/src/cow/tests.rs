@@ -1,4 +1,4 @@
-use cosmwasm_std::{testing::MockStorage, Addr, Order};
+use cosmwasm_std::{testing::MockStorage, Addr, Binary, Order, StdResult, Storage};
 use cw_storage_plus::{Bound, Index, IndexList, U64Key};
 use serde::{Deserialize, Serialize};
 
@@ -319,3 +319,701 @@ fn indexed_map_works() {
         None
     );
 }
+
+#[test]
+fn indexed_map_range_works() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+
+    let first = ToIndex {
+        id: 0,
+        count: 5,
+        address: Addr::unchecked("a"),
+    };
+
+    let second = ToIndex {
+        id: 1,
+        count: 6,
+        address: Addr::unchecked("b"),
+    };
+
+    it.indexed_map
+        .save(&mut storage, first.id.into(), &first)
+        .unwrap();
+    it.indexed_map
+        .save(&mut storage, second.id.into(), &second)
+        .unwrap();
+
+    assert_eq!(
+        it.indexed_map
+            .range(&storage, None, None, Order::Descending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(1, second.clone()), (0, first.clone())]
+    );
+
+    assert_eq!(
+        it.indexed_map
+            .keys(
+                &storage,
+                Some(Bound::exclusive_int(0u64)),
+                None,
+                Order::Ascending
+            )
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![1]
+    );
+
+    assert_eq!(
+        it.indexed_map
+            .range_raw(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap().0)
+            .collect::<Vec<_>>(),
+        it.indexed_map
+            .keys_raw(&storage, None, None, Order::Ascending)
+            .collect::<Vec<_>>()
+    );
+
+    let idm = it.indexed_map.indexed_map();
+    assert_eq!(
+        idm.idx
+            .count
+            .prefix(6.into())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap().1)
+            .collect::<Vec<_>>(),
+        vec![second]
+    );
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .address
+            .item(&storage, Addr::unchecked("a"))
+            .unwrap()
+            .unwrap()
+            .1,
+        first
+    );
+}
+
+#[test]
+fn indexed_map_prefix_range_works() {
+    let mut storage = MockStorage::new();
+    let idm: IndexedMapCow<(Addr, U64Key), ToIndex, ToIndexList> = IndexedMapCow::new_owned(
+        "owned".to_string(),
+        ToIndexList {
+            count: MultiIndexCow::new_owned(
+                "owned".to_string(),
+                "owned-count".to_string(),
+                |e, k| (e.count.into(), k),
+            ),
+            address: UniqueIndexCow::new_owned("owned-addr".to_string(), |e| {
+                Addr::unchecked(format!("{}-{}", e.address, e.id))
+            }),
+        },
+    );
+
+    let a = Addr::unchecked("a");
+    let b = Addr::unchecked("b");
+
+    for (id, address) in [(0u64, &a), (1, &b), (2, &a)] {
+        let data = ToIndex {
+            id,
+            count: 1,
+            address: address.clone(),
+        };
+        idm.save(&mut storage, (address.clone(), id.into()), &data)
+            .unwrap();
+    }
+
+    assert_eq!(
+        idm.prefix_range(&storage, a.clone(), None, None, Order::Ascending)
+            .map(|e| e.unwrap().0)
+            .collect::<Vec<_>>(),
+        vec![(a.clone(), 0), (a.clone(), 2)]
+    );
+
+    assert_eq!(
+        idm.prefix_range(
+            &storage,
+            a.clone(),
+            Some(Bound::exclusive_int(0u64)),
+            None,
+            Order::Ascending
+        )
+        .map(|e| e.unwrap().0)
+        .collect::<Vec<_>>(),
+        vec![(a.clone(), 2)]
+    );
+
+    assert_eq!(
+        idm.sub_prefix_range(&storage, (), None, None, Order::Descending)
+            .map(|e| e.unwrap().0)
+            .collect::<Vec<_>>(),
+        vec![(b, 1), (a.clone(), 2), (a, 0)]
+    );
+}
+
+#[test]
+fn unique_index_range_works() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+
+    let first = ToIndex {
+        id: 0,
+        count: 5,
+        address: Addr::unchecked("b"),
+    };
+
+    let second = ToIndex {
+        id: 1,
+        count: 5,
+        address: Addr::unchecked("a"),
+    };
+
+    it.indexed_map
+        .save(&mut storage, first.id.into(), &first)
+        .unwrap();
+    it.indexed_map
+        .save(&mut storage, second.id.into(), &second)
+        .unwrap();
+
+    let address = &it.indexed_map.index.address;
+
+    assert_eq!(
+        address
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![
+            (
+                Addr::unchecked("a"),
+                1u64.to_be_bytes().to_vec(),
+                second.clone()
+            ),
+            (
+                Addr::unchecked("b"),
+                0u64.to_be_bytes().to_vec(),
+                first.clone()
+            ),
+        ]
+    );
+
+    assert_eq!(
+        address
+            .keys(
+                &storage,
+                Some(Bound::exclusive("a")),
+                None,
+                Order::Ascending
+            )
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![Addr::unchecked("b")]
+    );
+
+    assert_eq!(
+        address
+            .load_by_index(&storage, Addr::unchecked("a"))
+            .unwrap(),
+        1u64.to_be_bytes().to_vec()
+    );
+    assert_eq!(
+        address.may_load_pk(&storage, Addr::unchecked("c")).unwrap(),
+        None
+    );
+    assert!(address
+        .load_by_index(&storage, Addr::unchecked("c"))
+        .is_err());
+}
+
+#[test]
+fn reindex_works() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let primary: MapCow<U64Key, ToIndex> = MapCow::new_ref("primary-idm");
+    let address_progress: ItemCow<ReindexProgress> = ItemCow::new_ref("primary-reindex-addr");
+    let count_progress: ItemCow<ReindexProgress> = ItemCow::new_ref("primary-reindex-count");
+
+    let data = |id: u64| ToIndex {
+        id,
+        count: id % 2,
+        address: Addr::unchecked(format!("addr{}", id)),
+    };
+
+    // records saved before the indexes existed
+    for id in 0..4 {
+        primary.save(&mut storage, id.into(), &data(id)).unwrap();
+    }
+    // record saved through the indexed map, already indexed
+    it.indexed_map
+        .save(&mut storage, 4.into(), &data(4))
+        .unwrap();
+
+    let reindex = |storage: &mut MockStorage| {
+        let idx = it.indexed_map.idx();
+        it.indexed_map
+            .reindex(storage, &idx.address, &address_progress, 2)
+            .unwrap();
+        it.indexed_map
+            .reindex(storage, &idx.count, &count_progress, 2)
+            .unwrap()
+    };
+
+    let state = reindex(&mut storage);
+    assert_eq!(state.count, 2);
+    assert!(!state.done);
+    assert_eq!(
+        address_progress.load(&storage).unwrap().cursor,
+        Some(Cursor(Binary(1u64.to_be_bytes().to_vec())))
+    );
+
+    reindex(&mut storage);
+    let state = reindex(&mut storage);
+    assert_eq!(state.count, 5);
+    assert!(state.done);
+    assert_eq!(reindex(&mut storage), state);
+
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .count
+            .prefix(0.into())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap().1.id)
+            .collect::<Vec<_>>(),
+        vec![0, 2, 4]
+    );
+    for id in 0..5 {
+        assert_eq!(
+            it.indexed_map
+                .idx()
+                .address
+                .item(&storage, Addr::unchecked(format!("addr{}", id)))
+                .unwrap()
+                .unwrap()
+                .1,
+            data(id)
+        );
+    }
+}
+
+#[test]
+fn reindex_rejects_unique_collision() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let primary: MapCow<U64Key, ToIndex> = MapCow::new_ref("primary-idm");
+    let progress: ItemCow<ReindexProgress> = ItemCow::new_ref("primary-reindex-addr");
+
+    let data = |id: u64| ToIndex {
+        id,
+        count: id,
+        address: Addr::unchecked("x"),
+    };
+
+    // record saved before the index existed, then one saved through the indexed map
+    primary.save(&mut storage, 0.into(), &data(0)).unwrap();
+    it.indexed_map
+        .save(&mut storage, 1.into(), &data(1))
+        .unwrap();
+
+    let err = it
+        .indexed_map
+        .reindex(&mut storage, &it.indexed_map.idx().address, &progress, 10)
+        .unwrap_err();
+    assert!(err.to_string().contains("Violates unique constraint"));
+    assert_eq!(progress.may_load(&storage).unwrap(), None);
+
+    // the entry of the indexed record is kept
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .address
+            .item(&storage, Addr::unchecked("x"))
+            .unwrap()
+            .unwrap()
+            .1,
+        data(1)
+    );
+}
+
+#[test]
+fn reindex_collision_discards_batch() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let primary: MapCow<U64Key, ToIndex> = MapCow::new_ref("primary-idm");
+    let progress: ItemCow<ReindexProgress> = ItemCow::new_ref("primary-reindex-addr");
+    let address = &it.indexed_map.idx().address;
+
+    let data = |id: u64, address: &str| ToIndex {
+        id,
+        count: id,
+        address: Addr::unchecked(address),
+    };
+
+    // records 0 and 1 predate the index, record 1 collides with record 2
+    primary.save(&mut storage, 0.into(), &data(0, "a")).unwrap();
+    primary.save(&mut storage, 1.into(), &data(1, "x")).unwrap();
+    it.indexed_map
+        .save(&mut storage, 2.into(), &data(2, "x"))
+        .unwrap();
+
+    let err = it
+        .indexed_map
+        .reindex(&mut storage, address, &progress, 10)
+        .unwrap_err();
+    assert!(err.to_string().contains("Violates unique constraint"));
+    assert_eq!(progress.may_load(&storage).unwrap(), None);
+    assert_eq!(address.item(&storage, Addr::unchecked("a")).unwrap(), None);
+}
+
+#[test]
+fn reindex_rejects_zero_limit() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let progress: ItemCow<ReindexProgress> = ItemCow::new_ref("primary-reindex-addr");
+
+    let err = it
+        .indexed_map
+        .reindex(&mut storage, &it.indexed_map.idx().address, &progress, 0)
+        .unwrap_err();
+    assert!(err.to_string().contains("limit must be greater than 0"));
+    assert_eq!(progress.may_load(&storage).unwrap(), None);
+}
+
+#[test]
+fn verify_and_repair_works() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let primary: MapCow<U64Key, ToIndex> = MapCow::new_ref("primary-idm");
+    let idx = it.indexed_map.idx();
+    let indexes: [&dyn VerifyIndex<ToIndex>; 2] = [&idx.count, &idx.address];
+
+    let data = |id: u64, address: &str| ToIndex {
+        id,
+        count: 1,
+        address: Addr::unchecked(address),
+    };
+
+    for id in 0..3 {
+        it.indexed_map
+            .save(&mut storage, id.into(), &data(id, &format!("addr{}", id)))
+            .unwrap();
+    }
+    assert!(it
+        .indexed_map
+        .verify(&storage, &indexes)
+        .unwrap()
+        .iter()
+        .all(IndexReport::is_consistent));
+
+    // drift: a removed record, an unindexed one and two records sharing an address
+    primary.remove(&mut storage, 0.into());
+    primary
+        .save(&mut storage, 3.into(), &data(3, "addr3"))
+        .unwrap();
+    primary
+        .save(&mut storage, 4.into(), &data(4, "addr2"))
+        .unwrap();
+
+    let pk = |id: u64| Binary(id.to_be_bytes().to_vec());
+    let count_key = |id: u64| Binary(idx.count.index_key((1.into(), pk(id).0)));
+    let address_key = |a: &str| Binary(idx.address.index_key(Addr::unchecked(a)));
+
+    let reports = it.indexed_map.repair(&mut storage, &indexes).unwrap();
+    assert_eq!(
+        reports,
+        vec![
+            IndexReport {
+                namespace: "primary-idm-count".to_string(),
+                orphaned: vec![count_key(0)],
+                missing: vec![pk(3), pk(4)],
+                collisions: vec![],
+                stale: vec![],
+            },
+            IndexReport {
+                namespace: "primary-idm-addr".to_string(),
+                orphaned: vec![address_key("addr0")],
+                missing: vec![pk(3)],
+                collisions: vec![(address_key("addr2"), vec![pk(2), pk(4)])],
+                stale: vec![],
+            },
+        ]
+    );
+
+    let reports = it.indexed_map.verify(&storage, &indexes).unwrap();
+    assert!(reports[0].is_consistent());
+    assert_eq!(reports[1].collisions.len(), 1);
+    assert!(reports[1].orphaned.is_empty() && reports[1].missing.is_empty());
+    assert_eq!(
+        idx.count
+            .prefix(1.into())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap().1.id)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn verify_and_repair_stale_unique_copy() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+    let primary: MapCow<U64Key, ToIndex> = MapCow::new_ref("primary-idm");
+    let idx = it.indexed_map.idx();
+
+    let mut data = ToIndex {
+        id: 0,
+        count: 1,
+        address: Addr::unchecked("a"),
+    };
+    it.indexed_map.save(&mut storage, 0.into(), &data).unwrap();
+
+    // record changed outside the map, the unique index keeps the old copy
+    data.id = 1;
+    primary.save(&mut storage, 0.into(), &data).unwrap();
+
+    let address_key = Binary(idx.address.index_key(Addr::unchecked("a")));
+    let reports = it.indexed_map.verify(&storage, &[&idx.address]).unwrap();
+    assert_eq!(reports[0].stale, vec![address_key]);
+    assert!(reports[0].orphaned.is_empty() && reports[0].missing.is_empty());
+
+    it.indexed_map
+        .repair(&mut storage, &[&idx.address])
+        .unwrap();
+    assert!(it.indexed_map.verify(&storage, &[&idx.address]).unwrap()[0].is_consistent());
+    assert_eq!(
+        idx.address
+            .item(&storage, Addr::unchecked("a"))
+            .unwrap()
+            .unwrap()
+            .1,
+        data
+    );
+}
+
+#[test]
+fn drop_index_works() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+
+    for id in 0..5 {
+        let data = ToIndex {
+            id,
+            count: id,
+            address: Addr::unchecked(format!("addr{}", id)),
+        };
+        it.indexed_map.save(&mut storage, id.into(), &data).unwrap();
+    }
+
+    let count = |storage: &MockStorage| {
+        it.indexed_map
+            .idx()
+            .count
+            .sub_prefix(())
+            .keys(storage, None, None, Order::Ascending)
+            .count()
+    };
+
+    assert!(!drop_index(&mut storage, "primary-idm-count", 2));
+    assert_eq!(count(&storage), 3);
+    assert!(!drop_index(&mut storage, "primary-idm-count", 2));
+    assert!(drop_index(&mut storage, "primary-idm-count", 2));
+    assert_eq!(count(&storage), 0);
+    assert!(drop_index(&mut storage, "primary-idm-count", 2));
+
+    // other namespaces are untouched
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .address
+            .keys(&storage, None, None, Order::Ascending)
+            .count(),
+        5
+    );
+    assert_eq!(
+        it.indexed_map
+            .keys_raw(&storage, None, None, Order::Ascending)
+            .count(),
+        5
+    );
+}
+
+#[test]
+fn drop_index_limits() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+
+    for id in 0..3 {
+        let data = ToIndex {
+            id,
+            count: id,
+            address: Addr::unchecked(format!("addr{}", id)),
+        };
+        it.indexed_map.save(&mut storage, id.into(), &data).unwrap();
+    }
+    let count = |storage: &MockStorage| {
+        it.indexed_map
+            .idx()
+            .count
+            .sub_prefix(())
+            .keys(storage, None, None, Order::Ascending)
+            .count()
+    };
+
+    // a zero limit only checks whether the namespace is empty
+    assert!(!drop_index(&mut storage, "primary-idm-count", 0));
+    assert_eq!(count(&storage), 3);
+
+    assert!(drop_index(&mut storage, "primary-idm-count", usize::MAX));
+    assert_eq!(count(&storage), 0);
+    assert!(drop_index(&mut storage, "primary-idm-count", 0));
+}
+
+/// `MockStorage` counting the writes and removes it receives.
+#[derive(Default)]
+struct CountingStorage {
+    inner: MockStorage,
+    writes: usize,
+    removes: usize,
+}
+
+impl Storage for CountingStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.inner.get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = cosmwasm_std::Pair> + 'a> {
+        self.inner.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes += 1;
+        self.inner.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.removes += 1;
+        self.inner.remove(key)
+    }
+}
+
+#[test]
+fn replace_skips_unchanged_indexes() {
+    let mut storage = CountingStorage::default();
+    let it = ItemMapAccessor::new("primary");
+
+    let mut data = ToIndex {
+        id: 0,
+        count: 5,
+        address: Addr::unchecked("a"),
+    };
+    it.indexed_map.save(&mut storage, 0.into(), &data).unwrap();
+    assert_eq!((storage.writes, storage.removes), (3, 0));
+
+    // same data, only the primary record is written
+    storage.writes = 0;
+    it.indexed_map.save(&mut storage, 0.into(), &data).unwrap();
+    assert_eq!((storage.writes, storage.removes), (1, 0));
+
+    // non indexed field, the unique index stores a copy of the value
+    storage.writes = 0;
+    data.id = 1;
+    it.indexed_map.save(&mut storage, 0.into(), &data).unwrap();
+    assert_eq!((storage.writes, storage.removes), (2, 0));
+
+    // indexed field
+    storage.writes = 0;
+    data.count = 6;
+    it.indexed_map.save(&mut storage, 0.into(), &data).unwrap();
+    assert_eq!((storage.writes, storage.removes), (3, 1));
+
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .count
+            .prefix(6.into())
+            .range(&storage, None, None, Order::Ascending)
+            .map(|e| e.unwrap().1)
+            .collect::<Vec<_>>(),
+        vec![data.clone()]
+    );
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .address
+            .item(&storage, Addr::unchecked("a"))
+            .unwrap()
+            .unwrap()
+            .1,
+        data
+    );
+
+    it.indexed_map.remove(&mut storage, 0.into()).unwrap();
+    assert_eq!(
+        it.indexed_map
+            .idx()
+            .address
+            .item(&storage, Addr::unchecked("a"))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn replace_is_atomic() {
+    let mut storage = MockStorage::new();
+    let it = ItemMapAccessor::new("primary");
+
+    let a = ToIndex {
+        id: 0,
+        count: 5,
+        address: Addr::unchecked("a"),
+    };
+    let b = ToIndex {
+        id: 1,
+        count: 6,
+        address: Addr::unchecked("b"),
+    };
+    it.indexed_map.save(&mut storage, 0.into(), &a).unwrap();
+    it.indexed_map.save(&mut storage, 1.into(), &b).unwrap();
+    let before = storage
+        .range(None, None, Order::Ascending)
+        .collect::<Vec<_>>();
+
+    // the count index is staged before the address index fails
+    let err = it
+        .indexed_map
+        .save(
+            &mut storage,
+            1.into(),
+            &ToIndex {
+                count: 7,
+                ..a.clone()
+            },
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Violates unique constraint"));
+    assert_eq!(
+        storage
+            .range(None, None, Order::Ascending)
+            .collect::<Vec<_>>(),
+        before
+    );
+    assert!(it
+        .indexed_map
+        .verify(
+            &storage,
+            &[&it.indexed_map.idx().count, &it.indexed_map.idx().address]
+        )
+        .unwrap()
+        .iter()
+        .all(IndexReport::is_consistent));
+    assert_eq!(it.indexed_map.load(&storage, 1.into()).unwrap(), b);
+}
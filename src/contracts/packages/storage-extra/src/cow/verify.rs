@@ -0,0 +1,148 @@
+use cosmwasm_std::{Binary, Order, Pair, StdResult, Storage};
+use cw_storage_plus::{range_with_prefix, Index};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::helpers::namespaces_with_key;
+
+/// Index whose entries can be cross-checked against the records of its map by `verify`.
+pub trait VerifyIndex<T>: Index<T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    fn idx_namespace(&self) -> &str;
+
+    /// Raw key `data` is stored under in the index, `None` if `data` is not indexed.
+    fn entry_key(&self, pk: &[u8], data: &T) -> Option<Vec<u8>>;
+
+    /// pk stored in the value of an entry, `None` for indexes keeping the pk in the key.
+    fn entry_pk(&self, _value: &[u8]) -> StdResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Raw value `data` is stored with in the index, `None` for indexes whose value does not
+    /// depend on the record.
+    fn entry_value(&self, _pk: &[u8], _data: &T) -> StdResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+/// Inconsistencies found by `verify` in one index.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    pub namespace: String,
+    /// Raw keys of entries pointing to a missing record, or to a record now indexed elsewhere.
+    pub orphaned: Vec<Binary>,
+    /// pks of records missing from the index.
+    pub missing: Vec<Binary>,
+    /// Raw keys shared by several records, like unique constraint violations, with their pks.
+    pub collisions: Vec<(Binary, Vec<Binary>)>,
+    /// Raw keys of entries whose copy of the record, like the value of a `UniqueIndexCow`,
+    /// differs from the record.
+    pub stale: Vec<Binary>,
+}
+
+impl IndexReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphaned.is_empty()
+            && self.missing.is_empty()
+            && self.collisions.is_empty()
+            && self.stale.is_empty()
+    }
+}
+
+pub(crate) fn verify_index<T: Serialize + DeserializeOwned + Clone>(
+    store: &dyn Storage,
+    records: &[Pair<T>],
+    index: &dyn VerifyIndex<T>,
+) -> StdResult<IndexReport> {
+    let mut report = IndexReport {
+        namespace: index.idx_namespace().to_string(),
+        ..IndexReport::default()
+    };
+
+    let mut expected: BTreeMap<Vec<u8>, Vec<&Pair<T>>> = BTreeMap::new();
+    for record in records {
+        if let Some(key) = index.entry_key(&record.0, &record.1) {
+            expected.entry(key).or_default().push(record);
+        }
+    }
+
+    let prefix = namespaces_with_key(&[index.idx_namespace().as_bytes()], b"");
+    let mut found = BTreeSet::new();
+    for (key, value) in range_with_prefix(store, &prefix, None, None, Order::Ascending) {
+        let record = match expected.get(&key) {
+            Some(records) => match index.entry_pk(&value)? {
+                Some(pk) => records.iter().find(|(p, _)| *p == pk),
+                None => records.first(),
+            },
+            None => None,
+        };
+        match record {
+            Some((pk, data)) => {
+                if index.entry_value(pk, data)?.map_or(false, |v| v != value) {
+                    report.stale.push(Binary(key.clone()));
+                }
+                found.insert(key);
+            }
+            None => report.orphaned.push(Binary(key)),
+        }
+    }
+
+    for (key, records) in expected {
+        let pks = records.into_iter().map(|(pk, _)| Binary(pk.clone()));
+        if pks.len() > 1 {
+            report.collisions.push((Binary(key), pks.collect()));
+        } else if !found.contains(&key) {
+            report.missing.extend(pks);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Remove the orphaned entries of `report`, index its missing records and rewrite its stale
+/// entries.
+pub(crate) fn repair_index<T: Serialize + DeserializeOwned + Clone>(
+    store: &mut dyn Storage,
+    records: &[Pair<T>],
+    index: &dyn VerifyIndex<T>,
+    report: &IndexReport,
+) -> StdResult<()> {
+    let idx_namespace = index.idx_namespace().as_bytes();
+    for key in &report.orphaned {
+        store.remove(&namespaces_with_key(&[idx_namespace], key.as_slice()));
+    }
+    let missing = report
+        .missing
+        .iter()
+        .map(Binary::as_slice)
+        .collect::<BTreeSet<_>>();
+    let stale = report
+        .stale
+        .iter()
+        .map(Binary::as_slice)
+        .collect::<BTreeSet<_>>();
+    for (pk, data) in records {
+        if missing.contains(pk.as_slice()) {
+            index.save(store, pk, data)?;
+        }
+
+        let key = match index.entry_key(pk, data) {
+            Some(key) if stale.contains(key.as_slice()) => key,
+            _ => continue,
+        };
+        // with collisions, only the record the entry points to is copied
+        let full_key = namespaces_with_key(&[idx_namespace], &key);
+        let stored_pk = match store.get(&full_key) {
+            Some(value) => index.entry_pk(&value)?,
+            None => None,
+        };
+        if stored_pk.map_or(true, |stored| stored == *pk) {
+            if let Some(value) = index.entry_value(pk, data)? {
+                store.set(&full_key, &value);
+            }
+        }
+    }
+    Ok(())
+}
@@ -1 +1,2 @@
 pub mod cow;
+pub mod paginate;
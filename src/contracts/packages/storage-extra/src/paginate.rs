@@ -0,0 +1,494 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Prefix};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::cow::{helpers::namespaces_with_key, Cursor, CursorPrefix, KeyDeserialize};
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PageRequest<K> {
+    pub start_after: Option<K>,
+    pub limit: Option<u32>,
+}
+
+impl<K> Default for PageRequest<K> {
+    fn default() -> Self {
+        Self {
+            start_after: None,
+            limit: None,
+        }
+    }
+}
+
+impl<K> PageRequest<K> {
+    pub fn new(start_after: Option<K>, limit: Option<u32>) -> Self {
+        Self { start_after, limit }
+    }
+
+    /// `limit` of this request, `default_limit` if not set, never more than `max_limit`.
+    pub fn clamp_limit(&self, default_limit: u32, max_limit: u32) -> usize {
+        self.limit.unwrap_or(default_limit).min(max_limit) as usize
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PageResponse<K, T> {
+    pub items: Vec<(K, T)>,
+    /// `start_after` of the next page, `None` if this is the last page.
+    pub next: Option<K>,
+}
+
+/// Page of an index range resumed by an opaque `Cursor` instead of the last pk.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CursorPageResponse<K, T> {
+    pub items: Vec<(K, T)>,
+    /// `start_after` of the next page, `None` if this is the last page.
+    pub next: Option<Cursor>,
+}
+
+/// Raw key of a deserialized key, used to turn `start_after` back into a range bound.
+pub trait PageKey {
+    fn raw_key(&self) -> Vec<u8>;
+}
+
+macro_rules! integer_page_key {
+    (for $($t:ty),+) => {
+        $(impl PageKey for $t {
+            fn raw_key(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
+        })*
+    }
+}
+
+integer_page_key!(for i8, u8, i16, u16, i32, u32, i64, u64, i128, u128);
+
+impl PageKey for Vec<u8> {
+    fn raw_key(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl PageKey for String {
+    fn raw_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl PageKey for Addr {
+    fn raw_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl<T: PageKey, U: PageKey> PageKey for (T, U) {
+    fn raw_key(&self) -> Vec<u8> {
+        namespaces_with_key(&[&self.0.raw_key()], &self.1.raw_key())
+    }
+}
+
+impl<T: PageKey, U: PageKey, V: PageKey> PageKey for (T, U, V) {
+    fn raw_key(&self) -> Vec<u8> {
+        namespaces_with_key(&[&self.0.raw_key(), &self.1.raw_key()], &self.2.raw_key())
+    }
+}
+
+/// Paginate over `prefix` with `DEFAULT_LIMIT` and `MAX_LIMIT`.
+///
+/// `start_after` is applied to the raw keys after `prefix`, so they must be the pk returned with
+/// each item, like for `MapCow::prefix`, `IndexedMapCow::prefix`, or `MultiIndexCow::prefix`
+/// and `ConditionalMultiIndex::prefix` without a custom `dese_fn`. `sub_prefix` ranges and
+/// `CustomDeseMultiIndex` store other bytes after the prefix and need `paginate_cursor`.
+///
+/// `K` is the key type of the raw keys returned by `prefix`.
+pub fn paginate<K, T>(
+    store: &dyn Storage,
+    prefix: Prefix<T>,
+    request: PageRequest<K::Output>,
+    order: Order,
+) -> StdResult<PageResponse<K::Output, T>>
+where
+    K: KeyDeserialize,
+    K::Output: PageKey + Clone,
+    T: Serialize + DeserializeOwned,
+{
+    paginate_with_limits::<K, T>(store, prefix, request, order, DEFAULT_LIMIT, MAX_LIMIT)
+}
+
+/// Like `paginate`, with custom default and max limit.
+pub fn paginate_with_limits<K, T>(
+    store: &dyn Storage,
+    prefix: Prefix<T>,
+    request: PageRequest<K::Output>,
+    order: Order,
+    default_limit: u32,
+    max_limit: u32,
+) -> StdResult<PageResponse<K::Output, T>>
+where
+    K: KeyDeserialize,
+    K::Output: PageKey + Clone,
+    T: Serialize + DeserializeOwned,
+{
+    let limit = request.clamp_limit(default_limit, max_limit);
+    let start = request.start_after.map(|k| Bound::Exclusive(k.raw_key()));
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+
+    // take one more item to know whether there is a next page
+    let mut items = prefix
+        .range(store, min, max, order)
+        .take(limit + 1)
+        .map(|kv| kv.and_then(|(k, v)| Ok((K::from_vec(k)?, v))))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let next = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|(k, _)| k.clone())
+    } else {
+        None
+    };
+
+    Ok(PageResponse { items, next })
+}
+
+/// Paginate over a `CursorPrefix` of `MultiIndexCow`, `CustomDeseMultiIndex` or
+/// `ConditionalMultiIndex` with `DEFAULT_LIMIT` and `MAX_LIMIT`.
+///
+/// `K` is the primary key type returned by the index `dese_fn`.
+pub fn paginate_cursor<K, T>(
+    store: &dyn Storage,
+    prefix: CursorPrefix<T>,
+    request: PageRequest<Cursor>,
+    order: Order,
+) -> StdResult<CursorPageResponse<K::Output, T>>
+where
+    K: KeyDeserialize,
+    T: Serialize + DeserializeOwned,
+{
+    paginate_cursor_with_limits::<K, T>(store, prefix, request, order, DEFAULT_LIMIT, MAX_LIMIT)
+}
+
+/// Like `paginate_cursor`, with custom default and max limit.
+pub fn paginate_cursor_with_limits<K, T>(
+    store: &dyn Storage,
+    prefix: CursorPrefix<T>,
+    request: PageRequest<Cursor>,
+    order: Order,
+    default_limit: u32,
+    max_limit: u32,
+) -> StdResult<CursorPageResponse<K::Output, T>>
+where
+    K: KeyDeserialize,
+    T: Serialize + DeserializeOwned,
+{
+    let limit = request.clamp_limit(default_limit, max_limit);
+    let start = request.start_after.map(|c| c.exclusive());
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+
+    // take one more item to know whether there is a next page
+    let mut items = prefix
+        .range(store, min, max, order)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let next = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|(c, _)| c.clone())
+    } else {
+        None
+    };
+
+    let items = items
+        .into_iter()
+        .map(|(_, (k, v))| Ok((K::from_vec(k)?, v)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CursorPageResponse { items, next })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cow::{
+        deserialize_multi_kv_custom_pk, ConditionalMultiIndex, CustomDeseMultiIndex, IndexedMapCow,
+        MapCow, MultiIndexCow, UniqueIndexCow,
+    };
+    use cosmwasm_std::testing::MockStorage;
+    use cw_storage_plus::{Index, IndexList, PrimaryKey, U64Key};
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Data {
+        id: u64,
+        count: u64,
+        address: Addr,
+    }
+
+    struct DataIndexes<'a> {
+        count: MultiIndexCow<'a, (U64Key, Vec<u8>), Data>,
+        address: UniqueIndexCow<'a, Addr, Data>,
+        big: ConditionalMultiIndex<'a, (U64Key, Vec<u8>), Data>,
+    }
+
+    impl IndexList<Data> for DataIndexes<'_> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Data>> + '_> {
+            let v: Vec<&dyn Index<Data>> = vec![&self.count, &self.address, &self.big];
+            Box::new(v.into_iter())
+        }
+    }
+
+    const DATA: IndexedMapCow<U64Key, Data, DataIndexes> = IndexedMapCow::new_ref(
+        "data",
+        DataIndexes {
+            count: MultiIndexCow::new_ref("data", "data__count", |d, k| (d.count.into(), k)),
+            address: UniqueIndexCow::new_ref("data__address", |d| d.address.clone()),
+            big: ConditionalMultiIndex::new_ref(
+                |d, k| (d.count.into(), k),
+                |d| d.id >= 2,
+                None,
+                "data",
+                "data__big",
+            ),
+        },
+    );
+
+    fn data(id: u64) -> Data {
+        Data {
+            id,
+            count: id % 2,
+            address: Addr::unchecked(format!("addr{}", id)),
+        }
+    }
+
+    fn setup(storage: &mut MockStorage) {
+        for id in 0..5 {
+            DATA.save(storage, id.into(), &data(id)).unwrap();
+        }
+    }
+
+    #[test]
+    fn clamp_limit() {
+        assert_eq!(PageRequest::<u64>::default().clamp_limit(10, 30), 10);
+        assert_eq!(
+            PageRequest::<u64>::new(None, Some(5)).clamp_limit(10, 30),
+            5
+        );
+        assert_eq!(
+            PageRequest::<u64>::new(None, Some(50)).clamp_limit(10, 30),
+            30
+        );
+    }
+
+    #[test]
+    fn paginate_map() {
+        let mut storage = MockStorage::new();
+        let map: MapCow<U64Key, u64> = MapCow::new_owned("map".to_string());
+        for i in 0..5u64 {
+            map.save(&mut storage, i.into(), &(i * 10)).unwrap();
+        }
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            map.prefix(()),
+            PageRequest::new(None, Some(2)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(0, 0), (1, 10)]);
+        assert_eq!(page.next, Some(1));
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            map.prefix(()),
+            PageRequest::new(page.next, Some(2)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(2, 20), (3, 30)]);
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            map.prefix(()),
+            PageRequest::new(page.next, Some(2)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(4, 40)]);
+        assert_eq!(page.next, None);
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            map.prefix(()),
+            PageRequest::new(Some(3), None),
+            Order::Descending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(2, 20), (1, 10), (0, 0)]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn paginate_indexed_map() {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        let page = paginate_with_limits::<U64Key, _>(
+            &storage,
+            DATA.prefix(()),
+            PageRequest::new(Some(0), Some(10)),
+            Order::Ascending,
+            1,
+            3,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(1, data(1)), (2, data(2)), (3, data(3))]);
+        assert_eq!(page.next, Some(3));
+    }
+
+    #[test]
+    fn paginate_indexes() {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            DATA.index.count.prefix(0.into()),
+            PageRequest::new(Some(0), Some(1)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(2, data(2))]);
+        assert_eq!(page.next, Some(2));
+
+        let page = paginate::<U64Key, _>(
+            &storage,
+            DATA.index.big.prefix(1.into()),
+            PageRequest::default(),
+            Order::Descending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(3, data(3))]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn paginate_multi_index_pages() {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        for (order, expected) in [
+            (Order::Ascending, vec![0, 2, 4]),
+            (Order::Descending, vec![4, 2, 0]),
+        ] {
+            let mut request = PageRequest::new(None, Some(1));
+            let mut ids = vec![];
+            loop {
+                let page = paginate::<U64Key, _>(
+                    &storage,
+                    DATA.index.count.prefix(0.into()),
+                    request,
+                    order,
+                )
+                .unwrap();
+                ids.extend(page.items.iter().map(|(k, d)| {
+                    assert_eq!(d, &data(*k));
+                    *k
+                }));
+                match page.next {
+                    Some(next) => request = PageRequest::new(Some(next), Some(1)),
+                    None => break,
+                }
+            }
+            assert_eq!(ids, expected);
+        }
+    }
+
+    #[test]
+    fn paginate_cursor_multi_index() {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        // pk alone can not resume a sub_prefix range, cursor can
+        let mut request = PageRequest::new(None, Some(2));
+        let mut ids = vec![];
+        loop {
+            let page = paginate_cursor::<U64Key, _>(
+                &storage,
+                DATA.index.count.cursor_sub_prefix(()),
+                request,
+                Order::Descending,
+            )
+            .unwrap();
+            ids.extend(page.items.iter().map(|(k, _)| *k));
+            match page.next {
+                Some(next) => {
+                    let next = Cursor::from_base64(&next.to_base64()).unwrap();
+                    request = PageRequest::new(Some(next), Some(2));
+                }
+                None => break,
+            }
+        }
+        assert_eq!(ids, vec![3, 1, 4, 2, 0]);
+
+        let page = paginate_cursor::<U64Key, _>(
+            &storage,
+            DATA.index.big.cursor_prefix(0.into()),
+            PageRequest::default(),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(page.items, vec![(2, data(2)), (4, data(4))]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn paginate_cursor_custom_dese() {
+        let mut storage = MockStorage::new();
+        let index: CustomDeseMultiIndex<(U64Key, Vec<u8>), Data> = CustomDeseMultiIndex::new_ref(
+            |d, _| (d.count.into(), U64Key::new(u64::MAX - d.id).joined_key()),
+            Some(|s, pk, kv| {
+                deserialize_multi_kv_custom_pk(s, pk, kv, |old_kv| {
+                    U64Key::new(u64::MAX - U64Key::from_vec(old_kv).unwrap()).joined_key()
+                })
+            }),
+            "data",
+            "data__custom",
+        );
+        for id in 0..5 {
+            let pk = U64Key::new(id).joined_key();
+            DATA.save(&mut storage, id.into(), &data(id)).unwrap();
+            index.save(&mut storage, &pk, &data(id)).unwrap();
+        }
+
+        let page = paginate_cursor::<U64Key, _>(
+            &storage,
+            index.cursor_sub_prefix(()),
+            PageRequest::new(None, Some(3)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(
+            page.items.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![4, 2, 0]
+        );
+
+        let page = paginate_cursor::<U64Key, _>(
+            &storage,
+            index.cursor_sub_prefix(()),
+            PageRequest::new(page.next, Some(3)),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(
+            page.items.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+        assert_eq!(page.next, None);
+    }
+}
@@ -0,0 +1,83 @@
+use cosmwasm_std::{testing::MockStorage, Addr, Order, StdResult};
+use cw_storage_plus::U64Key;
+use serde::{Deserialize, Serialize};
+use tw_storage_extra::cow::{ConditionalMultiIndex, IndexedMapCow, MultiIndexCow, UniqueIndexCow};
+use tw_storage_macros::IndexList;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Trade {
+    id: u64,
+    owner: Addr,
+    amount: u64,
+}
+
+#[derive(IndexList)]
+struct OrderIndexes<'a> {
+    owner: MultiIndexCow<'a, (Addr, Vec<u8>), Trade>,
+    id: UniqueIndexCow<'a, U64Key, self::Trade>,
+    large: ConditionalMultiIndex<'a, (U64Key, Vec<u8>), Trade>,
+    #[index(skip)]
+    label: String,
+}
+
+fn orders<'a>(ns: &str) -> IndexedMapCow<'a, U64Key, Trade, OrderIndexes<'a>> {
+    IndexedMapCow::new_owned(
+        ns.to_string(),
+        OrderIndexes {
+            owner: MultiIndexCow::new_owned(ns.to_string(), format!("{}__owner", ns), |o, k| {
+                (o.owner.clone(), k)
+            }),
+            id: UniqueIndexCow::new_owned(format!("{}__id", ns), |o| o.id.into()),
+            large: ConditionalMultiIndex::new_owned(
+                |o, k| (o.amount.into(), k),
+                |o| o.amount > 100,
+                None,
+                ns.to_string(),
+                format!("{}__large", ns),
+            ),
+            label: ns.to_string(),
+        },
+    )
+}
+
+#[test]
+fn derive_index_list() {
+    let mut storage = MockStorage::new();
+    let map = orders("orders");
+    assert_eq!(map.idx().label, "orders");
+
+    for (id, owner, amount) in [(1, "a", 50), (2, "a", 150), (3, "b", 200)] {
+        let order = Trade {
+            id,
+            owner: Addr::unchecked(owner),
+            amount,
+        };
+        map.save(&mut storage, id.into(), &order).unwrap();
+    }
+
+    let owned = map
+        .idx()
+        .owner
+        .prefix(Addr::unchecked("a"))
+        .range(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(owned.len(), 2);
+
+    let large = map
+        .idx()
+        .large
+        .sub_prefix(())
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| r.map(|(_, o)| o.id))
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(large, vec![2, 3]);
+
+    let dup = Trade {
+        id: 1,
+        owner: Addr::unchecked("c"),
+        amount: 10,
+    };
+    assert!(map.save(&mut storage, 4.into(), &dup).is_err());
+}
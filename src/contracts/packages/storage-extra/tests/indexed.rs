@@ -0,0 +1,77 @@
+use cosmwasm_std::{testing::MockStorage, Addr, Order, StdResult};
+use cw_storage_plus::U64Key;
+use serde::{Deserialize, Serialize};
+use tw_storage_extra::cow::{IndexedMapCow, UniqueIndexCow};
+use tw_storage_macros::Indexed;
+
+#[derive(Indexed, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Position {
+    #[index(unique, key = U64Key)]
+    id: u64,
+    #[index(multi)]
+    owner: Addr,
+    #[index(multi, when = "is_open", key = U64Key)]
+    size: u64,
+    open: bool,
+}
+
+fn is_open(p: &Position) -> bool {
+    p.open
+}
+
+fn position(id: u64, owner: &str, size: u64, open: bool) -> Position {
+    Position {
+        id,
+        owner: Addr::unchecked(owner),
+        size,
+        open,
+    }
+}
+
+#[test]
+fn derive_indexed() {
+    let mut storage = MockStorage::new();
+    let map: IndexedMapCow<U64Key, Position, PositionIndexes> = Position::indexed_map("pos");
+
+    map.save(&mut storage, 1.into(), &position(1, "a", 50, true))
+        .unwrap();
+    map.save(&mut storage, 2.into(), &position(2, "a", 20, false))
+        .unwrap();
+    map.save(&mut storage, 3.into(), &position(3, "b", 10, true))
+        .unwrap();
+
+    let by_id: UniqueIndexCow<U64Key, Position> =
+        UniqueIndexCow::new_ref("pos-id", |p| p.id.into());
+    assert_eq!(
+        by_id.load_by_index(&storage, 3.into()).unwrap(),
+        3u64.to_be_bytes().to_vec()
+    );
+    assert_eq!(
+        map.idx().id.load_by_index(&storage, 2.into()).unwrap(),
+        2u64.to_be_bytes().to_vec()
+    );
+    assert!(map
+        .save(&mut storage, 4.into(), &position(1, "c", 1, true))
+        .is_err());
+
+    let owned = map
+        .idx()
+        .owner
+        .prefix(Addr::unchecked("a"))
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| r.map(|(_, p)| p.id))
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(owned, vec![1, 2]);
+
+    // closed positions are not indexed by size
+    let open = map
+        .idx()
+        .size
+        .sub_prefix(())
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| r.map(|(_, p)| p.id))
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(open, vec![3, 1]);
+}
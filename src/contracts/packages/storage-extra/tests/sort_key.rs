@@ -0,0 +1,142 @@
+use cosmwasm_std::{testing::MockStorage, Order, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Map, U64Key};
+use serde::{Deserialize, Serialize};
+use tw_storage_extra::cow::{CustomDeseMultiIndex, KeyDeserialize, SortKey, Sorted};
+use tw_storage_macros::SortKey;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct BookOrder {
+    id: u64,
+    market: String,
+    price: Uint128,
+    time: u64,
+}
+
+#[derive(SortKey, Debug, Clone, PartialEq)]
+struct BookKey {
+    #[sort(desc)]
+    price: Uint128,
+    #[sort(asc)]
+    time: u64,
+    #[sort(asc, pk)]
+    id: u64,
+}
+
+#[derive(SortKey, Debug, Clone, PartialEq)]
+struct NameKey(#[sort(desc)] String, #[sort(asc)] String);
+
+struct BookIndexes<'a> {
+    book: CustomDeseMultiIndex<'a, (Vec<u8>, Vec<u8>), BookOrder>,
+}
+
+impl IndexList<BookOrder> for BookIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<BookOrder>> + '_> {
+        let v: Vec<&dyn Index<BookOrder>> = vec![&self.book];
+        Box::new(v.into_iter())
+    }
+}
+
+fn book<'a>() -> IndexedMap<'a, U64Key, BookOrder, BookIndexes<'a>> {
+    IndexedMap::new(
+        "orders",
+        BookIndexes {
+            book: CustomDeseMultiIndex::new_ref(
+                |o, _| {
+                    let key = BookKey {
+                        price: o.price,
+                        time: o.time,
+                        id: o.id,
+                    };
+                    (o.market.as_bytes().to_vec(), key.to_sort_key())
+                },
+                Some(BookKey::deserialize_fn()),
+                "orders",
+                "orders__book",
+            ),
+        },
+    )
+}
+
+#[test]
+fn sort_key_roundtrip() {
+    let key = BookKey {
+        price: Uint128::new(100),
+        time: 7,
+        id: 1,
+    };
+    assert_eq!(BookKey::from_sort_key(&key.to_sort_key()).unwrap(), key);
+    assert_eq!(Sorted::new(&key).value().unwrap(), key);
+
+    let key = NameKey("a\0b".to_string(), "c".to_string());
+    assert_eq!(NameKey::from_sort_key(&key.to_sort_key()).unwrap(), key);
+
+    let mut raw = key.to_sort_key();
+    raw.push(1);
+    assert!(NameKey::from_sort_key(&raw).is_err());
+    assert!(BookKey::from_sort_key(&[0; 31]).is_err());
+}
+
+#[test]
+fn sort_key_mixed_ordering() {
+    let mut storage = MockStorage::new();
+    let map: Map<Sorted<NameKey>, u64> = Map::new("names");
+
+    let keys = [("a", "b"), ("b", "b"), ("a", "a"), ("ab", "a"), ("b", "a")];
+    for (i, (a, b)) in keys.iter().enumerate() {
+        let key = NameKey(a.to_string(), b.to_string());
+        map.save(&mut storage, key.into(), &(i as u64)).unwrap();
+    }
+
+    let sorted = map
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| {
+            let key = Sorted::<NameKey>::from_vec(r.unwrap().0).unwrap();
+            (key.0, key.1)
+        })
+        .collect::<Vec<_>>();
+    let expected = vec![("b", "a"), ("b", "b"), ("ab", "a"), ("a", "a"), ("a", "b")]
+        .into_iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(sorted, expected);
+}
+
+#[test]
+fn sort_key_deserialize_fn() {
+    let mut storage = MockStorage::new();
+    let orders = vec![
+        (1, "eth", 100, 5),
+        (2, "eth", 200, 9),
+        (3, "eth", 100, 3),
+        (4, "btc", 500, 1),
+    ];
+    for (id, market, price, time) in orders {
+        let order = BookOrder {
+            id,
+            market: market.to_string(),
+            price: Uint128::new(price),
+            time,
+        };
+        book().save(&mut storage, id.into(), &order).unwrap();
+    }
+
+    let ids = book()
+        .idx
+        .book
+        .prefix(b"eth".to_vec())
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| r.unwrap())
+        .map(|(pk, order)| {
+            assert_eq!(pk, order.id.to_be_bytes().to_vec());
+            order.id
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![2, 3, 1]);
+}
+
+#[test]
+fn sort_key_deserialize_fn_short_key_errs() {
+    let storage = MockStorage::new();
+    let kv = (vec![0, 1], cosmwasm_std::to_vec(&8u32).unwrap());
+    assert!((BookKey::deserialize_fn::<BookOrder>())(&storage, b"orders", kv).is_err());
+}
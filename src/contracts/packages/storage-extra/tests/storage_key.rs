@@ -0,0 +1,183 @@
+use cosmwasm_std::{testing::MockStorage, Addr, Order, StdResult};
+use cw_storage_plus::{PrimaryKey, U64Key, U8Key};
+use tw_storage_extra::cow::{KeyDeserialize, MapCow};
+use tw_storage_macros::StorageKey;
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+#[storage_key(cow)]
+enum Status {
+    Open = 1,
+    Closed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+#[storage_key(cow, string)]
+enum Side {
+    #[storage_key(rename = "bid")]
+    Buy,
+    #[storage_key(rename = "ask")]
+    Sell,
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+#[storage_key(cow)]
+enum Asset {
+    Native(String),
+    #[storage_key(id = 5)]
+    Token(Addr),
+    Pair {
+        base: String,
+        id: U64Key,
+    },
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+#[storage_key(cow)]
+struct PoolKey {
+    base: Addr,
+    quote: Addr,
+    fee: U64Key,
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+#[storage_key(cow)]
+struct OrderId(U64Key);
+
+#[test]
+fn unit_enum_keys() {
+    let mut storage = MockStorage::new();
+    let map: MapCow<Status, u64> = MapCow::new_ref("status");
+
+    map.save(&mut storage, Status::Closed, &2).unwrap();
+    map.save(&mut storage, Status::Open, &1).unwrap();
+    assert_eq!(Status::Closed.joined_key(), vec![2]);
+    assert_eq!(
+        map.range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(Status::Open, 1), (Status::Closed, 2)]
+    );
+    assert!(Status::from_slice(&[3]).is_err());
+}
+
+#[test]
+fn string_enum_keys() {
+    let mut storage = MockStorage::new();
+    let map: MapCow<&Side, u64> = MapCow::new_owned(String::from("side"));
+
+    map.save(&mut storage, &Side::Buy, &1).unwrap();
+    map.save(&mut storage, &Side::Sell, &2).unwrap();
+    assert_eq!(Side::Buy.joined_key(), b"bid".to_vec());
+    assert_eq!(
+        map.keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![Side::Sell, Side::Buy]
+    );
+}
+
+#[test]
+fn payload_enum_keys() {
+    let mut storage = MockStorage::new();
+    let map: MapCow<&Asset, u64> = MapCow::new_ref("assets");
+
+    let assets = vec![
+        Asset::Native("uluna".to_string()),
+        Asset::Native("uusd".to_string()),
+        Asset::Token(Addr::unchecked("token")),
+        Asset::Pair {
+            base: "uluna".to_string(),
+            id: 7.into(),
+        },
+        Asset::Unknown,
+    ];
+    for (i, asset) in assets.iter().enumerate() {
+        map.save(&mut storage, asset, &(i as u64)).unwrap();
+    }
+    for (i, asset) in assets.iter().enumerate() {
+        assert_eq!(map.load(&storage, asset).unwrap(), i as u64);
+    }
+    assert_eq!(assets[0].joined_key(), [&[0, 1, 0][..], b"uluna"].concat());
+    assert_eq!(Asset::Unknown.joined_key(), vec![7]);
+
+    let natives = map
+        .prefix(U8Key::new(0))
+        .range(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(natives, vec![(b"uluna".to_vec(), 0), (b"uusd".to_vec(), 1)]);
+    assert_eq!(
+        map.prefix(U8Key::new(5))
+            .keys(&storage, None, None, Order::Ascending)
+            .count(),
+        1
+    );
+
+    let all = map
+        .keys(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(all, assets);
+}
+
+#[test]
+fn struct_keys() {
+    let mut storage = MockStorage::new();
+    let map: MapCow<&PoolKey, u64> = MapCow::new_ref("pools");
+
+    let pool = |base: &str, quote: &str, fee: u64| PoolKey {
+        base: Addr::unchecked(base),
+        quote: Addr::unchecked(quote),
+        fee: fee.into(),
+    };
+    let keys = vec![pool("a", "b", 5), pool("a", "b", 30), pool("a", "c", 5)];
+    for (i, key) in keys.iter().enumerate() {
+        map.save(&mut storage, key, &(i as u64)).unwrap();
+    }
+    assert_eq!(
+        keys[0].joined_key(),
+        (&Addr::unchecked("a"), &Addr::unchecked("b"), U64Key::new(5)).joined_key()
+    );
+
+    let tiers = map
+        .prefix((Addr::unchecked("a"), Addr::unchecked("b")))
+        .range(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        tiers,
+        vec![
+            (5u64.to_be_bytes().to_vec(), 0),
+            (30u64.to_be_bytes().to_vec(), 1)
+        ]
+    );
+    assert_eq!(
+        map.sub_prefix(Addr::unchecked("a"))
+            .keys(&storage, None, None, Order::Ascending)
+            .count(),
+        3
+    );
+    assert_eq!(
+        map.keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        keys
+    );
+}
+
+#[test]
+fn newtype_keys() {
+    let mut storage = MockStorage::new();
+    let map: MapCow<OrderId, u64> = MapCow::new_ref("orders");
+
+    map.save(&mut storage, OrderId(2.into()), &2).unwrap();
+    map.save(&mut storage, OrderId(1.into()), &1).unwrap();
+    assert_eq!(OrderId(1.into()).joined_key(), U64Key::new(1).joined_key());
+    assert_eq!(
+        map.range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(OrderId(1.into()), 1), (OrderId(2.into()), 2)]
+    );
+}
@@ -0,0 +1,79 @@
+use cosmwasm_std::{testing::MockStorage, Addr, Order, StdResult};
+use cw_storage_plus::U64Key;
+use serde::{Deserialize, Serialize};
+use tw_storage_extra::cow::{IndexedMapCow, ItemCow, MapCow};
+use tw_storage_macros::{storage_layout, Indexed};
+
+#[derive(Indexed, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Deposit {
+    id: u64,
+    #[index(multi)]
+    owner: Addr,
+}
+
+#[storage_layout(prefix = "pool")]
+struct PoolLayout<'a, 'key> {
+    total: ItemCow<'a, u64>,
+    #[layout(name = "bal")]
+    balances: MapCow<'a, &'key Addr, u64>,
+}
+
+#[storage_layout]
+struct VaultLayout<'a> {
+    total: ItemCow<'a, u64>,
+    #[layout(with = "Deposit::indexed_map")]
+    deposits: IndexedMapCow<'a, U64Key, Deposit, DepositIndexes<'a>>,
+}
+
+const POOL: PoolLayout = PoolLayout::new_ref();
+
+#[test]
+fn layout_namespaces() {
+    let mut storage = MockStorage::new();
+    let owner = Addr::unchecked("a");
+
+    POOL.total.save(&mut storage, &1).unwrap();
+    POOL.balances.save(&mut storage, &owner, &2).unwrap();
+
+    let pool = PoolLayout::new_owned("pool");
+    assert_eq!(pool.total.load(&storage).unwrap(), 1);
+    assert_eq!(pool.balances.load(&storage, &owner).unwrap(), 2);
+
+    let total: ItemCow<u64> = ItemCow::new_ref("pool-total");
+    let balances: MapCow<&Addr, u64> = MapCow::new_ref("pool-bal");
+    assert_eq!(total.load(&storage).unwrap(), 1);
+    assert_eq!(balances.load(&storage, &owner).unwrap(), 2);
+
+    let other = PoolLayout::new_owned("other");
+    assert_eq!(other.total.may_load(&storage).unwrap(), None);
+}
+
+#[test]
+fn layout_with() {
+    let mut storage = MockStorage::new();
+    let vault = VaultLayout::new_owned("vault");
+    let deposit = Deposit {
+        id: 1,
+        owner: Addr::unchecked("a"),
+    };
+
+    vault.total.save(&mut storage, &5).unwrap();
+    vault
+        .deposits
+        .save(&mut storage, 1.into(), &deposit)
+        .unwrap();
+
+    let deposits: IndexedMapCow<U64Key, Deposit, DepositIndexes> =
+        Deposit::indexed_map("vault-deposits");
+    assert_eq!(deposits.load(&storage, 1.into()).unwrap(), deposit);
+    assert_eq!(
+        deposits
+            .idx()
+            .owner
+            .prefix(Addr::unchecked("a"))
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(1u64.to_be_bytes().to_vec(), deposit)]
+    );
+}
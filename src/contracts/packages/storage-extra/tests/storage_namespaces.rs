@@ -0,0 +1,51 @@
+use cosmwasm_std::{testing::MockStorage, Addr};
+use cw_storage_plus::U64Key;
+use serde::{Deserialize, Serialize};
+use tw_storage_extra::cow::{IndexedMapCow, ItemCow, MapCow, MultiIndexCow, UniqueIndexCow};
+use tw_storage_macros::{namespace, storage_namespaces, IndexList};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Order {
+    id: u64,
+    owner: Addr,
+}
+
+#[derive(IndexList)]
+struct OrderIndexes<'a> {
+    owner: MultiIndexCow<'a, (Addr, Vec<u8>), Order>,
+    id: UniqueIndexCow<'a, U64Key, Order>,
+}
+
+storage_namespaces! {
+    const CONFIG: ItemCow<u64> = ItemCow::new_ref("config");
+    const BALANCES: MapCow<&Addr, u64> = MapCow::new_ref(namespace!("pool", "balances"));
+    const ORDERS: IndexedMapCow<U64Key, Order, OrderIndexes> = IndexedMapCow::new_ref(
+        "orders",
+        OrderIndexes {
+            owner: MultiIndexCow::new_ref("orders", "orders__owner", |o, k| (o.owner.clone(), k)),
+            id: UniqueIndexCow::new_ref("orders__id", |o| o.id.into()),
+        },
+    );
+
+    #[storage_ns("pool-total")]
+    fn pool_total<'a>() -> ItemCow<'a, u64> {
+        ItemCow::new_owned(format!("{}-total", "pool"))
+    }
+}
+
+#[test]
+fn distinct_namespaces() {
+    let mut storage = MockStorage::new();
+    let owner = Addr::unchecked("a");
+
+    CONFIG.save(&mut storage, &1).unwrap();
+    BALANCES.save(&mut storage, &owner, &2).unwrap();
+    pool_total().save(&mut storage, &3).unwrap();
+    ORDERS
+        .save(&mut storage, 1.into(), &Order { id: 1, owner })
+        .unwrap();
+
+    assert_eq!(CONFIG.load(&storage).unwrap(), 1);
+    assert_eq!(pool_total().load(&storage).unwrap(), 3);
+    assert_eq!(ORDERS.load(&storage, 1.into()).unwrap().id, 1);
+}
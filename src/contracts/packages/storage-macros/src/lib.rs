@@ -1,28 +1,41 @@
 use proc_macro::TokenStream;
+use std::collections::HashMap;
 use syn::{
-    Ident,
     __private::{quote::quote, Span, TokenStream2},
-    parse_macro_input, DeriveInput, Fields, ItemStruct,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    DeriveInput, Expr, ExprLit, Fields, Ident, ItemStruct, Lit, Member, Token,
 };
 
-#[proc_macro_attribute]
-pub fn index_list_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as ItemStruct);
-
-    let ty = Ident::new(&attr.to_string(), Span::call_site());
-    let struct_ty = input.ident.clone();
+fn expand_index_list_impl(ty: Ident, input: ItemStruct) -> syn::Result<TokenStream2> {
+    let struct_ty = &input.ident;
+    if input.generics.lifetimes().count() != 1 {
+        let span = if input.generics.params.is_empty() {
+            struct_ty.span()
+        } else {
+            input.generics.span()
+        };
+        return Err(syn::Error::new(
+            span,
+            "#[index_list_impl] expects a struct with one lifetime, like `struct Indexes<'a>`",
+        ));
+    }
 
     let names = input
         .fields
-        .clone()
-        .into_iter()
-        .map(|e| {
-            let name = e.ident.unwrap();
-            quote! { &self.#name }
+        .iter()
+        .map(|e| match &e.ident {
+            Some(name) => Ok(quote! { &self.#name }),
+            None => Err(syn::Error::new(
+                e.span(),
+                "#[index_list_impl] only supports structs with named fields",
+            )),
         })
-        .collect::<Vec<_>>();
+        .collect::<syn::Result<Vec<_>>>()?;
 
-    let expanded = quote! {
+    Ok(quote! {
         #input
 
         impl cw_storage_plus::IndexList<#ty> for #struct_ty<'_> {
@@ -31,101 +44,1835 @@ pub fn index_list_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
                 Box::new(v.into_iter())
             }
         }
+    })
+}
+
+#[proc_macro_attribute]
+pub fn index_list_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let ty = parse_macro_input!(attr as Ident);
+    let input = parse_macro_input!(item as ItemStruct);
+
+    expand_index_list_impl(ty, input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Value type `T` of an index field, the second type argument of `MultiIndex<'a, K, T, PK>`,
+/// `UniqueIndexCow<'a, K, T>` and the other indexes.
+fn index_value_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
     };
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .nth(1),
+        _ => None,
+    }
+}
 
-    TokenStream::from(expanded)
+fn is_index_skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("index")) {
+        let args = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        for arg in args {
+            if arg != "skip" {
+                return Err(syn::Error::new(arg.span(), "expected `skip`"));
+            }
+            skip = true;
+        }
+    }
+    Ok(skip)
 }
 
-#[proc_macro_derive(StorageKey)]
-pub fn derive_storage_key(item: TokenStream) -> TokenStream {
+fn expand_index_list(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        syn::Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "#[derive(IndexList)] only supports structs",
+            ))
+        }
+    };
+
+    let mut value_ty = input
+        .attrs
+        .iter()
+        .find(|a| a.path.is_ident("index_list"))
+        .map(|a| a.parse_args::<syn::Type>())
+        .transpose()?;
+
+    let mut members = vec![];
+    for (index, field) in fields.iter().enumerate() {
+        if is_index_skipped(field)? {
+            continue;
+        }
+
+        if value_ty.is_none() {
+            let inferred = index_value_type(&field.ty).ok_or_else(|| {
+                syn::Error::new(
+                    field.ty.span(),
+                    "cannot infer the value type of this index, add `#[index(skip)]` if it is not an index or set it with `#[index_list(T)]`",
+                )
+            })?;
+            value_ty = Some(inferred.clone());
+        }
+
+        members.push(match &field.ident {
+            Some(name) => Member::Named(name.clone()),
+            None => Member::Unnamed(index.into()),
+        });
+    }
+
+    let value_ty = value_ty.ok_or_else(|| {
+        syn::Error::new(
+            ident.span(),
+            "#[derive(IndexList)] needs at least one index field or `#[index_list(T)]`",
+        )
+    })?;
+
+    Ok(quote! {
+        impl #impl_generics cw_storage_plus::IndexList<#value_ty> for #ident #ty_generics #where_clause {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn cw_storage_plus::Index<#value_ty>> + '_> {
+                let v: Vec<&dyn cw_storage_plus::Index<#value_ty>> = vec![#(&self.#members),*];
+                Box::new(v.into_iter())
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(IndexList, attributes(index_list, index))]
+pub fn derive_index_list(item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as DeriveInput);
+
+    expand_index_list(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Integer type of enum ids, `#[storage_key(repr = u8 | u16 | u32)]`, `u8` when not set.
+#[derive(Clone, Copy)]
+enum Repr {
+    U8,
+    U16,
+    U32,
+}
+
+impl Repr {
+    fn parse(value: &Expr) -> syn::Result<Self> {
+        match value {
+            Expr::Path(path) if path.path.is_ident("u8") => Ok(Repr::U8),
+            Expr::Path(path) if path.path.is_ident("u16") => Ok(Repr::U16),
+            Expr::Path(path) if path.path.is_ident("u32") => Ok(Repr::U32),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "expected `u8`, `u16` or `u32`",
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Repr::U8 => "u8",
+            Repr::U16 => "u16",
+            Repr::U32 => "u32",
+        }
+    }
+
+    fn max(self) -> u32 {
+        match self {
+            Repr::U8 => u8::MAX.into(),
+            Repr::U16 => u16::MAX.into(),
+            Repr::U32 => u32::MAX,
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Repr::U8 => 1,
+            Repr::U16 => 2,
+            Repr::U32 => 4,
+        }
+    }
+
+    fn ty(self) -> Ident {
+        Ident::new(self.name(), Span::call_site())
+    }
+
+    /// `Key` variant holding an id.
+    fn key(self) -> TokenStream2 {
+        match self {
+            Repr::U8 => quote! { cw_storage_plus::Key::Val8 },
+            Repr::U16 => quote! { cw_storage_plus::Key::Val16 },
+            Repr::U32 => quote! { cw_storage_plus::Key::Val32 },
+        }
+    }
+
+    fn lit(self, id: u32) -> syn::LitInt {
+        syn::LitInt::new(&format!("{}{}", id, self.name()), Span::call_site())
+    }
+
+    /// Big endian bytes of an id, as a `'static` byte string.
+    fn bytes(self, id: u32) -> syn::LitByteStr {
+        let bytes = id.to_be_bytes();
+        syn::LitByteStr::new(&bytes[4 - self.width()..], Span::call_site())
+    }
+
+    /// Format string of an unknown id, zero padded to the id width.
+    fn unknown_fmt(self) -> String {
+        format!("unknown discriminant {{:#0{}x}}", 2 + 2 * self.width())
+    }
+}
+
+/// Traits implemented by `#[derive(StorageKey)]`, the `cw-storage-plus` 0.13 ones by default or,
+/// with `#[storage_key(cow)]`, the 0.9 ones used by `MapCow` and `IndexedMapCow` of
+/// `tw-storage-extra` along with its `KeyDeserialize`.
+#[derive(Clone, Copy)]
+enum KeyTarget {
+    Plus,
+    Cow,
+}
+
+impl KeyTarget {
+    /// Type of the segments returned by `PrimaryKey::key`.
+    fn segment(self) -> TokenStream2 {
+        match self {
+            KeyTarget::Plus => quote! { cw_storage_plus::Key },
+            KeyTarget::Cow => quote! { &[u8] },
+        }
+    }
+
+    fn key_deserialize(self) -> TokenStream2 {
+        match self {
+            KeyTarget::Plus => quote! { cw_storage_plus::KeyDeserialize },
+            KeyTarget::Cow => quote! { tw_storage_extra::cow::KeyDeserialize },
+        }
+    }
+
+    /// Key segment holding an enum id.
+    fn id(self, repr: Repr, id: u32) -> TokenStream2 {
+        match self {
+            KeyTarget::Plus => {
+                let (key, lit) = (repr.key(), repr.lit(id));
+                quote! { #key(#lit.to_be_bytes()) }
+            }
+            KeyTarget::Cow => {
+                let bytes = repr.bytes(id);
+                quote! { &#bytes[..] }
+            }
+        }
+    }
+
+    /// Prefix type of an enum id, `U8Key`..`U32Key` in 0.9 which has no integer keys.
+    fn id_ty(self, repr: Repr) -> TokenStream2 {
+        match (self, repr) {
+            (KeyTarget::Plus, repr) => {
+                let ty = repr.ty();
+                quote! { #ty }
+            }
+            (KeyTarget::Cow, Repr::U8) => quote! { cw_storage_plus::U8Key },
+            (KeyTarget::Cow, Repr::U16) => quote! { cw_storage_plus::U16Key },
+            (KeyTarget::Cow, Repr::U32) => quote! { cw_storage_plus::U32Key },
+        }
+    }
+}
+
+/// One `name` or `name = value` option of `#[storage_key(...)]`.
+struct KeyOption {
+    name: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for KeyOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(KeyOption { name, value })
+    }
+}
+
+/// Options of `#[storage_key(...)]`, on the enum or on one of its variants.
+#[derive(Default)]
+struct StorageKeyAttrs {
+    /// `explicit`, every variant must set its id.
+    explicit: bool,
+    /// `id = N`, storage id of a variant.
+    id: Option<syn::LitInt>,
+    /// `repr = u16`, integer type of the ids.
+    repr: Option<(Repr, Span)>,
+    /// `string`, variants are stored as their name.
+    string: Option<Span>,
+    /// `rename = "..."`, stored name of a variant in `string` mode.
+    rename: Option<syn::LitStr>,
+    /// `cow`, implements the traits of `tw-storage-extra`.
+    cow: Option<Span>,
+}
+
+impl StorageKeyAttrs {
+    fn target(&self) -> KeyTarget {
+        match self.cow {
+            Some(_) => KeyTarget::Cow,
+            None => KeyTarget::Plus,
+        }
+    }
+}
+
+fn parse_storage_key_attrs(attrs: &[syn::Attribute]) -> syn::Result<StorageKeyAttrs> {
+    let mut out = StorageKeyAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("storage_key")) {
+        let options = attr.parse_args_with(Punctuated::<KeyOption, Token![,]>::parse_terminated)?;
+        for KeyOption { name, value } in options {
+            match (name.to_string().as_str(), value) {
+                ("explicit", None) => out.explicit = true,
+                (
+                    "id",
+                    Some(Expr::Lit(ExprLit {
+                        lit: Lit::Int(id), ..
+                    })),
+                ) => out.id = Some(id),
+                ("id", value) => {
+                    return Err(syn::Error::new(
+                        value.map_or(name.span(), |v| v.span()),
+                        "expected an integer id, `id = N`",
+                    ))
+                }
+                ("repr", Some(value)) => out.repr = Some((Repr::parse(&value)?, value.span())),
+                ("string", None) => out.string = Some(name.span()),
+                ("cow", None) => out.cow = Some(name.span()),
+                (
+                    "rename",
+                    Some(Expr::Lit(ExprLit {
+                        lit: Lit::Str(rename),
+                        ..
+                    })),
+                ) => out.rename = Some(rename),
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "unknown storage_key option, expected `explicit`, `id = N`, `repr = u8 | u16 | u32`, `string`, `rename = \"...\"` or `cow`",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Storage id of every variant, from `A = N` discriminants, `#[storage_key(id = N)]` or, like
+/// Rust discriminants, the previous id plus one.
+fn variant_ids(
+    container: &StorageKeyAttrs,
+    variants: &Punctuated<syn::Variant, Token![,]>,
+) -> syn::Result<Vec<u32>> {
+    let repr = container.repr.map_or(Repr::U8, |(repr, _)| repr);
+    let mut seen: HashMap<u32, &Ident> = HashMap::new();
+    let mut next = Some(0u32);
+    let mut ids = Vec::with_capacity(variants.len());
+
+    for variant in variants {
+        let attrs = parse_storage_key_attrs(&variant.attrs)?;
+        if let Some(rename) = attrs.rename {
+            return Err(syn::Error::new(
+                rename.span(),
+                "`rename` is only supported with `#[storage_key(string)]`",
+            ));
+        }
+        if attrs.explicit || attrs.repr.is_some() || attrs.string.is_some() || attrs.cow.is_some() {
+            return Err(syn::Error::new(
+                variant.span(),
+                "`explicit`, `repr`, `string` and `cow` must be set on the enum, not on a variant",
+            ));
+        }
+
+        let (id, span) = match (attrs.id, &variant.discriminant) {
+            (Some(id), Some(_)) => {
+                return Err(syn::Error::new(
+                    id.span(),
+                    "variant has both a discriminant and a `#[storage_key(id)]`",
+                ))
+            }
+            (Some(id), None) => (id.base10_parse::<u32>()?, id.span()),
+            (
+                None,
+                Some((
+                    _,
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(id), ..
+                    }),
+                )),
+            ) => (id.base10_parse::<u32>()?, id.span()),
+            (None, Some((_, expr))) => {
+                return Err(syn::Error::new(
+                    expr.span(),
+                    "discriminant must be an integer literal",
+                ))
+            }
+            (None, None) if container.explicit => {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "missing explicit id, add a discriminant or `#[storage_key(id = N)]`",
+                ))
+            }
+            (None, None) => (
+                next.ok_or_else(|| {
+                    syn::Error::new(variant.ident.span(), "storage key id overflows u32")
+                })?,
+                variant.ident.span(),
+            ),
+        };
+
+        if id > repr.max() {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "storage key id {} does not fit in {}, set a wider `#[storage_key(repr = ...)]`",
+                    id,
+                    repr.name()
+                ),
+            ));
+        }
+        if let Some(other) = seen.insert(id, &variant.ident) {
+            return Err(syn::Error::new(
+                variant.ident.span(),
+                format!("storage key id {} is already used by `{}`", id, other),
+            ));
+        }
+        next = id.checked_add(1);
+        ids.push(id);
+    }
+
+    Ok(ids)
+}
+
+/// Statement reading the `id` bytes of a key into an integer of `repr`, with a parse error on an
+/// unexpected length.
+fn read_id(repr: Repr, name: &str) -> TokenStream2 {
+    let (ty, width) = (repr.ty(), repr.width());
+    quote! {
+        let id = match <[u8; #width]>::try_from(id) {
+            Ok(bytes) => #ty::from_be_bytes(bytes),
+            Err(_) => {
+                return Err(cosmwasm_std::StdError::parse_err(
+                    #name,
+                    format!("expected {} byte discriminant, got {} bytes", #width, id.len()),
+                ))
+            }
+        };
+    }
+}
+
+/// Nested `split_key` fn for the generated `try_from_slice`, splitting the first length
+/// prefixed segment from a joined key.
+fn split_key_fn(name: &str) -> TokenStream2 {
+    quote! {
+        fn split_key(b: &[u8]) -> cosmwasm_std::StdResult<(&[u8], &[u8])> {
+            if b.len() < 2 {
+                return Err(cosmwasm_std::StdError::parse_err(
+                    #name,
+                    "could not read 2 byte length",
+                ));
+            }
+            let (len, rest) = b.split_at(2);
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+            if rest.len() < len {
+                return Err(cosmwasm_std::StdError::parse_err(
+                    #name,
+                    "key is shorter than its length prefix",
+                ));
+            }
+            Ok(rest.split_at(len))
+        }
+    }
+}
+
+/// `PrimaryKey`, `Prefixer` and `KeyDeserialize` impls for both `T` and `&'a T`, on top of the
+/// generated `storage_key` and `try_from_slice`. The 0.9 `PrimaryKey` has no `Suffix` and
+/// `SuperSuffix`.
+fn key_impls(
+    target: KeyTarget,
+    ident: &Ident,
+    prefix: TokenStream2,
+    sub_prefix: TokenStream2,
+    suffix: TokenStream2,
+    super_suffix: TokenStream2,
+) -> TokenStream2 {
+    let (segment, key_deserialize) = (target.segment(), target.key_deserialize());
+    let suffixes = match target {
+        KeyTarget::Plus => quote! {
+            type Suffix = #suffix;
+            type SuperSuffix = #super_suffix;
+        },
+        KeyTarget::Cow => quote! {},
+    };
+
+    quote! {
+        impl<'a> cw_storage_plus::PrimaryKey<'a> for #ident {
+            type Prefix = #prefix;
+            type SubPrefix = #sub_prefix;
+            #suffixes
+
+            fn key(&self) -> Vec<#segment> {
+                self.storage_key()
+            }
+        }
+
+        impl<'a> cw_storage_plus::PrimaryKey<'a> for &'a #ident {
+            type Prefix = #prefix;
+            type SubPrefix = #sub_prefix;
+            #suffixes
+
+            fn key(&self) -> Vec<#segment> {
+                self.storage_key()
+            }
+        }
+
+        impl<'a> cw_storage_plus::Prefixer<'a> for #ident {
+            fn prefix(&self) -> Vec<#segment> {
+                self.storage_key()
+            }
+        }
+
+        impl<'a> cw_storage_plus::Prefixer<'a> for &'a #ident {
+            fn prefix(&self) -> Vec<#segment> {
+                self.storage_key()
+            }
+        }
+
+        impl #key_deserialize for #ident {
+            type Output = Self;
+
+            fn from_vec(value: Vec<u8>) -> cosmwasm_std::StdResult<Self::Output> {
+                #ident::try_from_slice(&value)
+            }
+        }
+
+        impl #key_deserialize for &#ident {
+            type Output = #ident;
+
+            fn from_vec(value: Vec<u8>) -> cosmwasm_std::StdResult<Self::Output> {
+                #ident::try_from_slice(&value)
+            }
+        }
+    }
+}
+
+/// Type of a run of key fields, `()` when empty and the field itself when alone.
+fn key_tuple(tys: &[&syn::Type]) -> TokenStream2 {
+    match tys {
+        [ty] => quote! { #ty },
+        tys => quote! { (#(#tys),*) },
+    }
+}
+
+/// Compile time check that every field type is a single segment key, rejecting keys with a
+/// `Prefix`, like tuples or other composite keys, that would not split back from a joined key.
+fn single_segment_check(tys: &[&syn::Type]) -> TokenStream2 {
+    quote! {
+        const _: fn() = || {
+            fn single_segment_key<'a, K: cw_storage_plus::PrimaryKey<'a, Prefix = ()>>() {}
+            #(single_segment_key::<#tys>();)*
+        };
+    }
+}
+
+/// `StorageKey` for single field structs, delegating every impl to the key of the field.
+fn expand_newtype_key(target: KeyTarget, ident: &Ident, field: &syn::Field) -> TokenStream2 {
+    let ty = &field.ty;
+    let member = match &field.ident {
+        Some(name) => Member::Named(name.clone()),
+        None => Member::Unnamed(0.into()),
+    };
+    let (segment, key_deserialize) = (target.segment(), target.key_deserialize());
+    let suffixes = match target {
+        KeyTarget::Plus => quote! {
+            type Suffix = <#ty as cw_storage_plus::PrimaryKey<'a>>::Suffix;
+            type SuperSuffix = <#ty as cw_storage_plus::PrimaryKey<'a>>::SuperSuffix;
+        },
+        KeyTarget::Cow => quote! {},
+    };
+
+    quote! {
+        impl<'a> cw_storage_plus::PrimaryKey<'a> for #ident {
+            type Prefix = <#ty as cw_storage_plus::PrimaryKey<'a>>::Prefix;
+            type SubPrefix = <#ty as cw_storage_plus::PrimaryKey<'a>>::SubPrefix;
+            #suffixes
+
+            fn key(&self) -> Vec<#segment> {
+                cw_storage_plus::PrimaryKey::key(&self.#member)
+            }
+        }
+
+        impl<'a> cw_storage_plus::PrimaryKey<'a> for &'a #ident {
+            type Prefix = <#ty as cw_storage_plus::PrimaryKey<'a>>::Prefix;
+            type SubPrefix = <#ty as cw_storage_plus::PrimaryKey<'a>>::SubPrefix;
+            #suffixes
+
+            fn key(&self) -> Vec<#segment> {
+                cw_storage_plus::PrimaryKey::key(&self.#member)
+            }
+        }
+
+        impl<'a> cw_storage_plus::Prefixer<'a> for #ident {
+            fn prefix(&self) -> Vec<#segment> {
+                cw_storage_plus::Prefixer::prefix(&self.#member)
+            }
+        }
+
+        impl<'a> cw_storage_plus::Prefixer<'a> for &'a #ident {
+            fn prefix(&self) -> Vec<#segment> {
+                cw_storage_plus::Prefixer::prefix(&self.#member)
+            }
+        }
+
+        impl #key_deserialize for #ident {
+            type Output = Self;
+
+            fn from_vec(value: Vec<u8>) -> cosmwasm_std::StdResult<Self::Output> {
+                Ok(Self { #member: <#ty as #key_deserialize>::from_vec(value)?.into() })
+            }
+        }
+
+        impl #key_deserialize for &#ident {
+            type Output = #ident;
+
+            fn from_vec(value: Vec<u8>) -> cosmwasm_std::StdResult<Self::Output> {
+                <#ident as #key_deserialize>::from_vec(value)
+            }
+        }
+    }
+}
+
+/// `StorageKey` for structs as composite keys, with `Prefix`, `SubPrefix`, `Suffix` and
+/// `SuperSuffix` built from its fields like the tuple of the field types. Single field structs
+/// are newtypes of their field.
+fn expand_struct_key(
+    target: KeyTarget,
+    ident: &Ident,
+    fields: Fields,
+) -> syn::Result<TokenStream2> {
+    let n = fields.len();
+    if n == 0 {
+        return Err(syn::Error::new(
+            ident.span(),
+            "#[derive(StorageKey)] on structs needs at least one field",
+        ));
+    }
+    if let (1, Some(field)) = (n, fields.iter().next()) {
+        return Ok(expand_newtype_key(target, ident, field));
+    }
+    if n > 4 {
+        return Err(syn::Error::new(
+            fields.span(),
+            "#[derive(StorageKey)] on structs supports at most four fields",
+        ));
+    }
+
+    let name = ident.to_string();
+    let split_key = split_key_fn(&name);
+    let (segment, key_deserialize) = (target.segment(), target.key_deserialize());
+    let tys = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        })
+        .collect::<Vec<_>>();
+    let bindings = (0..n)
+        .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+        .collect::<Vec<_>>();
+    let (init, last) = (&bindings[..n - 1], &bindings[n - 1]);
+    let (init_tys, last_ty) = (&tys[..n - 1], tys[n - 1]);
+
+    let super_suffix = match n {
+        2 => quote! { #ident },
+        _ => key_tuple(&tys[n - 2..]),
+    };
+    let impls = key_impls(
+        target,
+        ident,
+        key_tuple(&tys[..n - 1]),
+        key_tuple(&tys[..n - 2]),
+        quote! { #last_ty },
+        super_suffix,
+    );
+    let check = single_segment_check(&tys);
+
+    Ok(quote! {
+        impl #ident {
+            fn storage_key(&self) -> Vec<#segment> {
+                let mut keys = Vec::new();
+                #(keys.extend(cw_storage_plus::PrimaryKey::key(&self.#members));)*
+                keys
+            }
+
+            pub fn try_from_slice(b: &[u8]) -> cosmwasm_std::StdResult<Self> {
+                #split_key
+
+                let rest = b;
+                #(
+                    let (raw, rest) = split_key(rest)?;
+                    let #init = <#init_tys as #key_deserialize>::from_slice(raw)?.into();
+                )*
+                let #last = <#last_ty as #key_deserialize>::from_slice(rest)?.into();
+
+                Ok(Self { #(#members: #bindings),* })
+            }
+        }
+
+        #impls
+
+        #check
+    })
+}
+
+/// `StorageKey` for enums with data-carrying variants, stored as the variant id followed by the
+/// keys of its fields. Unit variants are stored as their id alone, exactly like in an enum
+/// without data, and are told apart from the length prefixed id of the other variants by their
+/// length.
+fn expand_payload_enum(
+    target: KeyTarget,
+    ident: &Ident,
+    variants: Punctuated<syn::Variant, Token![,]>,
+    ids: Vec<u32>,
+    repr: Repr,
+) -> TokenStream2 {
+    let name = ident.to_string();
+    let split_key = split_key_fn(&name);
+    let read_id = read_id(repr, &name);
+    let (width, segment, key_deserialize) =
+        (repr.width(), target.segment(), target.key_deserialize());
+    let unknown_fmt = repr.unknown_fmt();
+    let impls = key_impls(
+        target,
+        ident,
+        target.id_ty(repr),
+        quote! { () },
+        quote! { Vec<u8> },
+        quote! { #ident },
+    );
+
+    let mut keys = Vec::with_capacity(variants.len());
+    let (mut units, mut unit_ids) = (Vec::new(), Vec::new());
+    let (mut payloads, mut payload_ids) = (Vec::new(), Vec::new());
+    let mut field_tys = Vec::new();
+    for (variant, data) in variants.iter().zip(ids) {
+        let id = &variant.ident;
+        let id_key = target.id(repr, data);
+        let data = repr.lit(data);
+        let bindings = (0..variant.fields.len())
+            .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+            .collect::<Vec<_>>();
+        let tys = variant.fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+        let pat = match &variant.fields {
+            Fields::Unit => quote! { Self::#id },
+            Fields::Unnamed(_) => quote! { Self::#id(#(#bindings),*) },
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|f| &f.ident);
+                quote! { Self::#id { #(#names: #bindings),* } }
+            }
+        };
+
+        let n = bindings.len();
+        if n == 0 {
+            keys.push(quote! { #pat => vec![#id_key] });
+            units.push(quote! { #data => Ok(#pat) });
+            unit_ids.push(data);
+            continue;
+        }
+
+        keys.push(quote! {
+            #pat => {
+                let mut keys = vec![#id_key];
+                #(keys.extend(cw_storage_plus::PrimaryKey::key(#bindings));)*
+                keys
+            }
+        });
+        let (init, last) = (&bindings[..n - 1], &bindings[n - 1]);
+        let (init_tys, last_ty) = (&tys[..n - 1], tys[n - 1]);
+        payloads.push(quote! {
+            #data => {
+                #(
+                    let (raw, rest) = split_key(rest)?;
+                    let #init = <#init_tys as #key_deserialize>::from_slice(raw)?.into();
+                )*
+                let #last = <#last_ty as #key_deserialize>::from_slice(rest)?.into();
+                Ok(#pat)
+            }
+        });
+        payload_ids.push(data);
+        field_tys.extend(tys);
+    }
+    let check = single_segment_check(&field_tys);
+
+    quote! {
+        impl #ident {
+            fn storage_key(&self) -> Vec<#segment> {
+                match self { #(#keys),* }
+            }
+
+            pub fn try_from_slice(b: &[u8]) -> cosmwasm_std::StdResult<Self> {
+                #split_key
+
+                if b.len() == #width {
+                    let id = b;
+                    #read_id
+                    return match id {
+                        #(#units,)*
+                        #(#payload_ids)|* => Err(cosmwasm_std::StdError::parse_err(
+                            #name,
+                            "missing fields after discriminant",
+                        )),
+                        other => Err(cosmwasm_std::StdError::parse_err(
+                            #name,
+                            format!(#unknown_fmt, other),
+                        )),
+                    };
+                }
+
+                let (id, rest) = split_key(b)?;
+                #read_id
+                match id {
+                    #(#payloads,)*
+                    #(#unit_ids => Err(cosmwasm_std::StdError::parse_err(
+                        #name,
+                        "unexpected fields after unit variant",
+                    )),)*
+                    other => Err(cosmwasm_std::StdError::parse_err(
+                        #name,
+                        format!(#unknown_fmt, other),
+                    )),
+                }
+            }
+        }
+
+        #impls
+
+        #check
+    }
+}
+
+/// `StorageKey` for unit enums in `string` mode, stored as the name of the variant so keys are
+/// readable from raw state and ordered lexicographically.
+fn expand_string_enum(
+    ident: &Ident,
+    container: &StorageKeyAttrs,
+    variants: Punctuated<syn::Variant, Token![,]>,
+) -> syn::Result<TokenStream2> {
+    if let Some((_, span)) = container.repr {
+        return Err(syn::Error::new(
+            span,
+            "`repr` is not supported with `string`",
+        ));
+    }
+    if container.explicit {
+        return Err(syn::Error::new(
+            ident.span(),
+            "`explicit` is not supported with `string`",
+        ));
+    }
+
+    let mut seen: HashMap<String, Ident> = HashMap::new();
+    let mut b = Vec::with_capacity(variants.len());
+    let mut b_rev = Vec::with_capacity(variants.len());
+    for variant in variants {
+        let id = variant.ident;
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new(
+                variant.fields.span(),
+                "`string` mode only supports unit variants",
+            ));
+        }
+        let attrs = parse_storage_key_attrs(&variant.attrs)?;
+        if let Some(id) = attrs.id {
+            return Err(syn::Error::new(
+                id.span(),
+                "`id` is not supported with `string`, use `rename`",
+            ));
+        }
+        if attrs.explicit || attrs.repr.is_some() || attrs.string.is_some() || attrs.cow.is_some() {
+            return Err(syn::Error::new(
+                id.span(),
+                "`explicit`, `repr`, `string` and `cow` must be set on the enum, not on a variant",
+            ));
+        }
+
+        let (stored, span) = match attrs.rename {
+            Some(rename) => (rename.value(), rename.span()),
+            None => (id.to_string(), id.span()),
+        };
+        if let Some(other) = seen.insert(stored.clone(), id.clone()) {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "storage key name \"{}\" is already used by `{}`",
+                    stored, other
+                ),
+            ));
+        }
+
+        let data = syn::LitByteStr::new(stored.as_bytes(), Span::call_site());
+        b.push(quote! { Self::#id => #data });
+        b_rev.push(quote! { #data => Ok(Self::#id) });
+    }
+
+    let name = ident.to_string();
+    let target = container.target();
+    let segment = target.segment();
+    let key = match target {
+        KeyTarget::Plus => quote! { cw_storage_plus::Key::Ref(self.as_bytes()) },
+        KeyTarget::Cow => quote! { self.as_bytes() },
+    };
+    let impls = key_impls(
+        target,
+        ident,
+        quote! { () },
+        quote! { () },
+        quote! { () },
+        quote! { () },
+    );
+    Ok(quote! {
+        impl #ident {
+            fn as_bytes(&self) -> &'static [u8] {
+                match self { #(#b),* }
+            }
+
+            fn storage_key(&self) -> Vec<#segment> {
+                vec![#key]
+            }
+
+            pub fn try_from_slice(b: &[u8]) -> cosmwasm_std::StdResult<Self> {
+                match b {
+                    #(#b_rev),*,
+                    other => Err(cosmwasm_std::StdError::parse_err(
+                        #name,
+                        format!("unknown variant name {:?}", String::from_utf8_lossy(other)),
+                    )),
+                }
+            }
+        }
+
+        #impls
+    })
+}
+
+/// `StorageKey` for unit enums, stored as the id of the variant.
+fn expand_unit_enum(
+    target: KeyTarget,
+    ident: &Ident,
+    variants: Punctuated<syn::Variant, Token![,]>,
+    ids: Vec<u32>,
+    repr: Repr,
+) -> TokenStream2 {
+    let mut b = Vec::with_capacity(variants.len());
+    let mut b_rev = Vec::with_capacity(variants.len());
+    let mut keys = Vec::with_capacity(variants.len());
+    for (variant, data) in variants.into_iter().zip(ids) {
+        let id = variant.ident;
+        let id_key = target.id(repr, data);
+        let data = repr.lit(data);
+
+        b.push(quote! { Self::#id => #data });
+        b_rev.push(quote! { #data => Ok(Self::#id) });
+        keys.push(quote! { Self::#id => #id_key });
+    }
+
+    let name = ident.to_string();
+    let read_id = read_id(repr, &name);
+    let (width, segment, unknown_fmt) = (repr.width(), target.segment(), repr.unknown_fmt());
+    let impls = key_impls(
+        target,
+        ident,
+        quote! { () },
+        quote! { () },
+        quote! { () },
+        quote! { () },
+    );
+    quote! {
+        impl #ident {
+            fn as_bytes(&self) -> [u8; #width] {
+                match self { #(#b),* }.to_be_bytes()
+            }
+
+            fn storage_key(&self) -> Vec<#segment> {
+                vec![match self { #(#keys),* }]
+            }
+
+            pub fn try_from_slice(b: &[u8]) -> cosmwasm_std::StdResult<Self> {
+                let id = b;
+                #read_id
+                match id {
+                    #(#b_rev),*,
+                    other => Err(cosmwasm_std::StdError::parse_err(
+                        #name,
+                        format!(#unknown_fmt, other),
+                    )),
+                }
+            }
+        }
+
+        #impls
+    }
+}
+
+fn expand_storage_key(input: DeriveInput) -> syn::Result<TokenStream2> {
     let ident = input.ident;
 
+    let container = parse_storage_key_attrs(&input.attrs)?;
+    if let Some(span) = container
+        .id
+        .as_ref()
+        .map(|id| id.span())
+        .or_else(|| container.rename.as_ref().map(|r| r.span()))
+    {
+        return Err(syn::Error::new(
+            span,
+            "`id` and `rename` must be set on a variant, not on the type",
+        ));
+    }
+
     match input.data {
+        syn::Data::Enum(data_enum) if data_enum.variants.is_empty() => Err(syn::Error::new(
+            ident.span(),
+            "#[derive(StorageKey)] needs at least one variant",
+        )),
+        syn::Data::Enum(data_enum) if container.string.is_some() => {
+            expand_string_enum(&ident, &container, data_enum.variants)
+        }
         syn::Data::Enum(data_enum) => {
-            let (b, b_rev): (Vec<TokenStream2>, Vec<TokenStream2>) = data_enum
+            let ids = variant_ids(&container, &data_enum.variants)?;
+            let repr = container.repr.map_or(Repr::U8, |(repr, _)| repr);
+            if data_enum
                 .variants
-                .into_iter()
-                .enumerate()
-                .map(|e| {
-                    let id = e.1.ident;
-                    let data = match e.1.fields {
-                        Fields::Unit => e.0 as u8,
-                        _ => panic!(
-                            "#[derive(StorageKey)] currently only support unit enum variants"
-                        ),
-                    };
+                .iter()
+                .any(|v| !matches!(v.fields, Fields::Unit))
+            {
+                Ok(expand_payload_enum(
+                    container.target(),
+                    &ident,
+                    data_enum.variants,
+                    ids,
+                    repr,
+                ))
+            } else {
+                Ok(expand_unit_enum(
+                    container.target(),
+                    &ident,
+                    data_enum.variants,
+                    ids,
+                    repr,
+                ))
+            }
+        }
+        syn::Data::Struct(data_struct) => {
+            if container.explicit || container.repr.is_some() || container.string.is_some() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "`explicit`, `repr` and `string` are only supported on enums",
+                ));
+            }
+            expand_struct_key(container.target(), &ident, data_struct.fields)
+        }
+        syn::Data::Union(data_union) => Err(syn::Error::new(
+            data_union.union_token.span(),
+            "#[derive(StorageKey)] only supports enums and structs",
+        )),
+    }
+}
 
-                    (
-                        quote! {
-                            Self::#id => #data
-                        },
-                        quote! {
-                            #data => Self::#id
-                        },
-                    )
-                })
-                .unzip();
+#[proc_macro_derive(StorageKey, attributes(storage_key))]
+pub fn derive_storage_key(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
 
-            let expanded = quote! {
-                impl #ident {
-                    fn as_bytes(&self) -> [u8; 1] {
-                        match self { #(#b),* }.to_be_bytes()
-                    }
+    expand_storage_key(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-                    fn from_slice(b: &[u8]) -> Self {
-                        match b[0] {
-                            #(#b_rev),*,
-                            _ => panic!("Should not be others")
-                        }
-                    }
+/// Field of a `#[derive(SortKey)]` struct.
+struct SortField {
+    member: Member,
+    ty: syn::Type,
+    desc: bool,
+    pk: bool,
+}
+
+fn parse_sort_field(index: usize, field: syn::Field) -> syn::Result<SortField> {
+    let span = field.span();
+    let mut order = None;
+    let mut pk = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("sort")) {
+        let args = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        for arg in args {
+            match arg.to_string().as_str() {
+                "asc" | "desc" if order.is_some() => {
+                    return Err(syn::Error::new(arg.span(), "sort order is already set"))
+                }
+                "asc" => order = Some(false),
+                "desc" => order = Some(true),
+                "pk" => pk = true,
+                _ => {
+                    return Err(syn::Error::new(
+                        arg.span(),
+                        "expected `asc`, `desc` or `pk`",
+                    ))
                 }
+            }
+        }
+    }
 
-                impl cw_storage_plus::PrimaryKey<'_> for #ident {
-                    type Prefix = ();
-                    type SubPrefix = ();
-                    type Suffix = ();
-                    type SuperSuffix = ();
+    let desc = order.ok_or_else(|| {
+        syn::Error::new(span, "missing `#[sort(asc)]` or `#[sort(desc)]` on field")
+    })?;
+    let member = match field.ident {
+        Some(ident) => Member::Named(ident),
+        None => Member::Unnamed(index.into()),
+    };
 
-                    fn key(&self) -> Vec<cw_storage_plus::Key> {
-                        vec![cw_storage_plus::Key::Val8(self.as_bytes())]
-                    }
+    Ok(SortField {
+        member,
+        ty: field.ty,
+        desc,
+        pk,
+    })
+}
+
+fn expand_sort_key(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match input.data {
+        syn::Data::Struct(data) if !data.fields.is_empty() => data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "#[derive(SortKey)] only supports structs with at least one field",
+            ))
+        }
+    };
+    let fields = fields
+        .into_iter()
+        .enumerate()
+        .map(|(i, f)| parse_sort_field(i, f))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let pk_fields = fields.iter().filter(|f| f.pk).collect::<Vec<_>>();
+    if pk_fields.len() > 1 {
+        return Err(syn::Error::new(
+            pk_fields[1].ty.span(),
+            "only one field can be marked as `pk`",
+        ));
+    }
+    if fields[..fields.len() - 1].iter().any(|f| f.pk) {
+        return Err(syn::Error::new(
+            pk_fields[0].ty.span(),
+            "the `pk` field must be the last field of the sort key",
+        ));
+    }
+
+    let encode = fields.iter().map(|f| {
+        let (member, desc) = (&f.member, f.desc);
+        quote! {
+            tw_storage_extra::cow::encode_sort_field(&self.#member, #desc, &mut out);
+        }
+    });
+    let vars = (0..fields.len())
+        .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+        .collect::<Vec<_>>();
+    let decode = fields.iter().zip(&vars).map(|(f, var)| {
+        let (ty, desc) = (&f.ty, f.desc);
+        quote! {
+            let #var = tw_storage_extra::cow::decode_sort_field::<#ty>(&mut raw, #desc)?;
+        }
+    });
+    let members = fields.iter().map(|f| &f.member);
+    let trailing_err = format!("Trailing bytes after {} sort key", ident);
+
+    let pk = pk_fields.first().map(|f| {
+        let (ty, desc) = (&f.ty, f.desc);
+        quote! {
+            fn pk_from_tail(tail: &[u8]) -> cosmwasm_std::StdResult<Vec<u8>> {
+                const _: () = assert!(
+                    <#ty as tw_storage_extra::cow::DescKey>::WIDTH.is_some(),
+                    "the `pk` field of a sort key must be fixed width"
+                );
+                tw_storage_extra::cow::decode_sort_pk::<#ty>(tail, #desc)
+            }
+        }
+    });
+    let de_fn = pk.as_ref().map(|_| {
+        quote! {
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// `DeserializeFn` recovering the pk from the last field of this sort key. Not a
+                /// `const fn`, use `deserialize_multi_kv_sort_pk::<Self, T>` in `const` items.
+                pub fn deserialize_fn<T: serde::de::DeserializeOwned>(
+                ) -> tw_storage_extra::cow::DeserializeFn<T> {
+                    tw_storage_extra::cow::deserialize_multi_kv_sort_pk::<Self, T>
                 }
+            }
+        }
+    });
 
-                impl<'a> cw_storage_plus::PrimaryKey<'a> for &'a #ident {
-                    type Prefix = ();
-                    type SubPrefix = ();
-                    type Suffix = ();
-                    type SuperSuffix = ();
+    Ok(quote! {
+        impl #impl_generics tw_storage_extra::cow::SortKey for #ident #ty_generics #where_clause {
+            fn to_sort_key(&self) -> Vec<u8> {
+                let mut out = Vec::new();
+                #(#encode)*
+                out
+            }
 
-                    fn key(&self) -> Vec<cw_storage_plus::Key> {
-                        vec![cw_storage_plus::Key::Val8(self.as_bytes())]
-                    }
+            fn from_sort_key(raw: &[u8]) -> cosmwasm_std::StdResult<Self> {
+                let mut raw = raw;
+                #(#decode)*
+                if !raw.is_empty() {
+                    return Err(cosmwasm_std::StdError::generic_err(#trailing_err));
                 }
 
-                impl cw_storage_plus::Prefixer<'_> for #ident {
-                    fn prefix(&self) -> Vec<cw_storage_plus::Key> {
-                        vec![cw_storage_plus::Key::Val8(self.as_bytes())]
-                    }
+                Ok(Self { #(#members: #vars),* })
+            }
+
+            #pk
+        }
+
+        #de_fn
+    })
+}
+
+#[proc_macro_derive(SortKey, attributes(sort))]
+pub fn derive_sort_key(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    expand_sort_key(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Kind of index of a `#[derive(Indexed)]` field.
+enum IndexKind {
+    Unique,
+    Multi,
+    /// `multi, when = "fn_name"`, a `ConditionalMultiIndex` using `fn_name` as `cond_fn`.
+    Conditional(syn::ExprPath),
+}
+
+/// Option of `#[index(...)]` on a `#[derive(Indexed)]` field.
+enum IndexOption {
+    Unique(Span),
+    Multi(Span),
+    When(syn::ExprPath),
+    Key(syn::Type),
+}
+
+impl Parse for IndexOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        match name.to_string().as_str() {
+            "unique" => Ok(IndexOption::Unique(name.span())),
+            "multi" => Ok(IndexOption::Multi(name.span())),
+            "when" => {
+                input.parse::<Token![=]>()?;
+                let lit: syn::LitStr = input.parse()?;
+                Ok(IndexOption::When(lit.parse()?))
+            }
+            "key" => {
+                input.parse::<Token![=]>()?;
+                Ok(IndexOption::Key(input.parse()?))
+            }
+            _ => Err(syn::Error::new(
+                name.span(),
+                "expected `unique`, `multi`, `when = \"fn_name\"` or `key = Type`",
+            )),
+        }
+    }
+}
+
+/// Indexed field of a `#[derive(Indexed)]` struct.
+struct IndexedField {
+    name: Ident,
+    ty: syn::Type,
+    kind: IndexKind,
+    /// `key = Type`, index key converted from the field with `Into`.
+    key: Option<syn::Type>,
+}
+
+fn parse_indexed_field(field: &syn::Field) -> syn::Result<Option<IndexedField>> {
+    let attr = match field.attrs.iter().find(|a| a.path.is_ident("index")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    let name = field.ident.clone().ok_or_else(|| {
+        syn::Error::new(
+            field.span(),
+            "#[derive(Indexed)] only supports structs with named fields",
+        )
+    })?;
+
+    let mut kind = None;
+    let mut when = None;
+    let mut key = None;
+    for option in attr.parse_args_with(Punctuated::<IndexOption, Token![,]>::parse_terminated)? {
+        match option {
+            IndexOption::Unique(span) | IndexOption::Multi(span) if kind.is_some() => {
+                return Err(syn::Error::new(span, "index kind is already set"))
+            }
+            IndexOption::Unique(span) => kind = Some((false, span)),
+            IndexOption::Multi(span) => kind = Some((true, span)),
+            IndexOption::When(path) => when = Some(path),
+            IndexOption::Key(ty) => key = Some(ty),
+        }
+    }
+
+    let kind = match (kind, when) {
+        (None, _) => {
+            return Err(syn::Error::new(
+                attr.span(),
+                "missing `unique` or `multi` in `#[index(...)]`",
+            ))
+        }
+        (Some((false, _)), Some(path)) => {
+            return Err(syn::Error::new(
+                path.span(),
+                "`when` is only supported on `multi` indexes",
+            ))
+        }
+        (Some((false, _)), None) => IndexKind::Unique,
+        (Some((true, _)), None) => IndexKind::Multi,
+        (Some((true, _)), Some(path)) => IndexKind::Conditional(path),
+    };
+
+    Ok(Some(IndexedField {
+        name,
+        ty: field.ty.clone(),
+        kind,
+        key,
+    }))
+}
+
+fn expand_indexed(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let vis = &input.vis;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "#[derive(Indexed)] does not support generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        syn::Data::Struct(data) => data
+            .fields
+            .iter()
+            .map(parse_indexed_field)
+            .filter_map(syn::Result::transpose)
+            .collect::<syn::Result<Vec<_>>>()?,
+        _ => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "#[derive(Indexed)] only supports structs",
+            ))
+        }
+    };
+    if fields.is_empty() {
+        return Err(syn::Error::new(
+            ident.span(),
+            "#[derive(Indexed)] needs at least one `#[index(unique)]` or `#[index(multi)]` field",
+        ));
+    }
+
+    let indexes = Ident::new(&format!("{}Indexes", ident), ident.span());
+    let names = fields.iter().map(|f| &f.name).collect::<Vec<_>>();
+
+    let mut types = vec![];
+    let mut ctors = vec![];
+    for field in &fields {
+        let name = &field.name;
+        let key = field.key.as_ref().unwrap_or(&field.ty);
+        let value = match field.key {
+            Some(_) => quote! { v.#name.clone().into() },
+            None => quote! { v.#name.clone() },
+        };
+        let idx_ns = quote! { format!("{}{}{}", ns, #NAMESPACE_SEPARATOR, stringify!(#name)) };
+
+        let (ty, ctor) = match &field.kind {
+            IndexKind::Unique => (
+                quote! { tw_storage_extra::cow::UniqueIndexCow<'a, #key, #ident> },
+                quote! { tw_storage_extra::cow::UniqueIndexCow::new_owned(#idx_ns, |v| #value) },
+            ),
+            IndexKind::Multi => (
+                quote! { tw_storage_extra::cow::MultiIndexCow<'a, (#key, Vec<u8>), #ident> },
+                quote! {
+                    tw_storage_extra::cow::MultiIndexCow::new_owned(
+                        ns.to_string(),
+                        #idx_ns,
+                        |v, pk| (#value, pk),
+                    )
+                },
+            ),
+            IndexKind::Conditional(cond) => (
+                quote! { tw_storage_extra::cow::ConditionalMultiIndex<'a, (#key, Vec<u8>), #ident> },
+                quote! {
+                    tw_storage_extra::cow::ConditionalMultiIndex::new_owned(
+                        |v, pk| (#value, pk),
+                        #cond,
+                        None,
+                        ns.to_string(),
+                        #idx_ns,
+                    )
+                },
+            ),
+        };
+        types.push(ty);
+        ctors.push(ctor);
+    }
+
+    Ok(quote! {
+        #vis struct #indexes<'a> {
+            #(pub #names: #types),*
+        }
+
+        impl cw_storage_plus::IndexList<#ident> for #indexes<'_> {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn cw_storage_plus::Index<#ident>> + '_> {
+                let v: Vec<&dyn cw_storage_plus::Index<#ident>> = vec![#(&self.#names),*];
+                Box::new(v.into_iter())
+            }
+        }
+
+        impl #ident {
+            /// Indexed map stored under `ns`, every index under `{ns}-{field}`.
+            #vis fn indexed_map<'a, K>(ns: &str) -> tw_storage_extra::cow::IndexedMapCow<'a, K, #ident, #indexes<'a>> {
+                tw_storage_extra::cow::IndexedMapCow::new_owned(
+                    ns.to_string(),
+                    #indexes {
+                        #(#names: #ctors),*
+                    },
+                )
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Indexed, attributes(index))]
+pub fn derive_indexed(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    expand_indexed(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Separator between the parts of composed namespaces, `pool-balances`.
+const NAMESPACE_SEPARATOR: &str = "-";
+
+fn join_namespace(parts: &[String]) -> String {
+    parts.join(NAMESPACE_SEPARATOR)
+}
+
+/// String value of a `name = "..."` option.
+fn lit_str_option(name: &Ident, value: Option<Expr>) -> syn::Result<syn::LitStr> {
+    match value {
+        Some(Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        })) => Ok(lit),
+        value => Err(syn::Error::new(
+            value.map_or(name.span(), |v| v.span()),
+            format!("expected a string, `{} = \"...\"`", name),
+        )),
+    }
+}
+
+/// Accessor field of a `#[storage_layout]` struct.
+struct LayoutField {
+    name: Ident,
+    ty: syn::Type,
+    /// Namespace suffix, the field name unless `#[layout(name = "...")]`.
+    suffix: syn::LitStr,
+    /// `#[layout(with = "path")]`, `fn(&str) -> Field` building the field from its namespace.
+    with: Option<syn::ExprPath>,
+}
+
+fn parse_layout_field(field: &mut syn::Field) -> syn::Result<LayoutField> {
+    let name = field.ident.clone().ok_or_else(|| {
+        syn::Error::new(
+            field.span(),
+            "#[storage_layout] only supports structs with named fields",
+        )
+    })?;
+    let mut suffix = syn::LitStr::new(&name.to_string(), name.span());
+    let mut with = None;
+
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("layout")) {
+        let options = attr.parse_args_with(Punctuated::<KeyOption, Token![,]>::parse_terminated)?;
+        for KeyOption { name, value } in options {
+            match name.to_string().as_str() {
+                "name" => suffix = lit_str_option(&name, value)?,
+                "with" => with = Some(lit_str_option(&name, value)?.parse()?),
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "unknown layout option, expected `name = \"...\"` or `with = \"path\"`",
+                    ))
                 }
+            }
+        }
+    }
+    field.attrs.retain(|a| !a.path.is_ident("layout"));
+
+    Ok(LayoutField {
+        name,
+        ty: field.ty.clone(),
+        suffix,
+        with,
+    })
+}
+
+fn expand_storage_layout(
+    options: Punctuated<KeyOption, Token![,]>,
+    mut input: ItemStruct,
+) -> syn::Result<TokenStream2> {
+    let mut prefix = None;
+    for KeyOption { name, value } in options {
+        match name.to_string().as_str() {
+            "prefix" => prefix = Some(lit_str_option(&name, value)?),
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown storage_layout option, expected `prefix = \"...\"`",
+                ))
+            }
+        }
+    }
+
+    let fields = match &mut input.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter_mut()
+            .map(parse_layout_field)
+            .collect::<syn::Result<Vec<_>>>()?,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[storage_layout] only supports structs with named fields",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let names = fields.iter().map(|f| &f.name).collect::<Vec<_>>();
+
+    let owned = fields.iter().map(|f| {
+        let (ty, suffix) = (&f.ty, &f.suffix);
+        let ns = quote! { format!("{}{}{}", prefix, #NAMESPACE_SEPARATOR, #suffix) };
+        match &f.with {
+            Some(with) => quote! { #with(&#ns) },
+            None => quote! { <#ty>::new_owned(#ns) },
+        }
+    });
 
-                impl<'a> cw_storage_plus::Prefixer<'a> for &'a #ident {
-                    fn prefix(&self) -> Vec<cw_storage_plus::Key> {
-                        vec![cw_storage_plus::Key::Val8(self.as_bytes())]
+    let new_ref = match prefix {
+        Some(prefix) => {
+            if let Some(f) = fields.iter().find(|f| f.with.is_some()) {
+                return Err(syn::Error::new(
+                    f.name.span(),
+                    "fields built `with` a function, like an `IndexedMapCow`, can not be constructed by the const `new_ref`, remove `prefix` to only generate `new_owned` or declare the field as its own const",
+                ));
+            }
+            let refs = fields.iter().map(|f| {
+                let ty = &f.ty;
+                let ns = join_namespace(&[prefix.value(), f.suffix.value()]);
+                let ns = syn::LitStr::new(&ns, f.suffix.span());
+                quote! { <#ty>::new_ref(#ns) }
+            });
+            let doc = format!("Layout under the `{}` prefix.", prefix.value());
+            quote! {
+                #[doc = #doc]
+                pub const fn new_ref() -> Self {
+                    Self {
+                        #(#names: #refs),*
                     }
                 }
+            }
+        }
+        None => quote! {},
+    };
+
+    Ok(quote! {
+        #input
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Layout under `prefix`, every field stored under `{prefix}-{field}`.
+            pub fn new_owned(prefix: &str) -> Self {
+                Self {
+                    #(#names: #owned),*
+                }
+            }
+
+            #new_ref
+        }
+    })
+}
+
+/// Generates `new_owned(prefix)` for a struct of accessors, and with `prefix = "..."` a const
+/// `new_ref()`. Fields built `#[layout(with = "path")]`, like an `IndexedMapCow` from
+/// `#[derive(Indexed)]` whose index namespaces are built with `format!`, are not supported by
+/// `new_ref`: a const `IndexedMapCow` is declared on its own with literal namespaces instead.
+#[proc_macro_attribute]
+pub fn storage_layout(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options =
+        parse_macro_input!(attr with Punctuated::<KeyOption, Token![,]>::parse_terminated);
+    let input = parse_macro_input!(item as ItemStruct);
+
+    expand_storage_layout(options, input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_namespace(parts: Punctuated<syn::LitStr, Token![,]>) -> syn::Result<TokenStream2> {
+    let first = parts.first().ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "namespace! expects at least one string, like `namespace!(\"pool\", \"balances\")`",
+        )
+    })?;
+    if let Some(empty) = parts.iter().find(|p| p.value().is_empty()) {
+        return Err(syn::Error::new(
+            empty.span(),
+            "namespace parts can not be empty",
+        ));
+    }
+
+    let ns = join_namespace(&parts.iter().map(syn::LitStr::value).collect::<Vec<_>>());
+    let ns = syn::LitStr::new(&ns, first.span());
+    Ok(quote! { #ns })
+}
+
+#[proc_macro]
+pub fn namespace(input: TokenStream) -> TokenStream {
+    let parts =
+        parse_macro_input!(input with Punctuated::<syn::LitStr, Token![,]>::parse_terminated);
 
-                impl cw_storage_plus::KeyDeserialize for #ident {
-                    type Output = Self;
+    expand_namespace(parts)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Namespaces declared by the items of `storage_namespaces!`.
+#[derive(Default)]
+struct NamespaceVisitor {
+    declared: Vec<(String, Span)>,
+    error: Option<syn::Error>,
+    /// In `const` and `static` initializers every call builds an accessor, elsewhere only
+    /// `is_accessor_constructor` calls do.
+    any_call: bool,
+}
+
+/// `Type::new`, `Type::new_ref` or `Type::new_owned` of a storage type, like `ItemCow` or
+/// `MultiIndex`, or an `indexed_map` fn generated by `#[derive(Indexed)]`.
+fn is_accessor_constructor(func: &Expr) -> bool {
+    let path = match func {
+        Expr::Path(path) => &path.path,
+        _ => return false,
+    };
+    let mut segments = path.segments.iter().rev();
+    match (segments.next(), segments.next()) {
+        (Some(f), _) if f.ident == "indexed_map" => true,
+        (Some(f), Some(ty)) => {
+            let ty = ty.ident.to_string();
+            ["new", "new_ref", "new_owned"].iter().any(|c| f.ident == c)
+                && ["Item", "Map", "Index", "Cow", "Deque"]
+                    .iter()
+                    .any(|suffix| ty.ends_with(suffix))
+        }
+        _ => false,
+    }
+}
 
-                    fn from_vec(value: Vec<u8>) -> cosmwasm_std::StdResult<Self::Output> {
-                        Ok(#ident::from_slice(&value))
+impl NamespaceVisitor {
+    fn push_error(&mut self, err: syn::Error) {
+        match &mut self.error {
+            Some(error) => error.combine(err),
+            None => self.error = Some(err),
+        }
+    }
+
+    /// Namespace of a string literal or `namespace!(...)` argument.
+    fn namespace(&mut self, expr: &Expr) -> Option<(String, Span)> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => Some((lit.value(), lit.span())),
+            Expr::Macro(m) if m.mac.path.segments.last()?.ident == "namespace" => {
+                match m
+                    .mac
+                    .parse_body_with(Punctuated::<syn::LitStr, Token![,]>::parse_terminated)
+                {
+                    Ok(parts) => Some((
+                        join_namespace(&parts.iter().map(syn::LitStr::value).collect::<Vec<_>>()),
+                        m.span(),
+                    )),
+                    Err(err) => {
+                        self.push_error(err);
+                        None
                     }
                 }
-            };
+            }
+            _ => None,
+        }
+    }
+
+    /// The last namespace argument of an accessor constructor is the one it declares, earlier
+    /// ones, like the pk namespace of a multi index, refer to other accessors.
+    fn declare_last<'ast>(&mut self, args: impl Iterator<Item = &'ast Expr>) {
+        let mut namespaces = args
+            .filter_map(|arg| self.namespace(arg))
+            .collect::<Vec<_>>();
+        if let Some((ns, span)) = namespaces.pop() {
+            if namespaces.iter().any(|(other, _)| *other == ns) {
+                self.push_error(syn::Error::new(
+                    span,
+                    format!("index namespace `{}` is the same as its pk namespace", ns),
+                ));
+            }
+            self.declared.push((ns, span));
+        }
+    }
+
+    fn visit_scoped(&mut self, any_call: bool, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.any_call, any_call);
+        visit(self);
+        self.any_call = outer;
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for NamespaceVisitor {
+    fn visit_item_const(&mut self, item: &'ast syn::ItemConst) {
+        self.visit_scoped(true, |v| syn::visit::visit_item_const(v, item));
+    }
+
+    fn visit_item_static(&mut self, item: &'ast syn::ItemStatic) {
+        self.visit_scoped(true, |v| syn::visit::visit_item_static(v, item));
+    }
+
+    fn visit_impl_item_const(&mut self, item: &'ast syn::ImplItemConst) {
+        self.visit_scoped(true, |v| syn::visit::visit_impl_item_const(v, item));
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.visit_scoped(false, |v| syn::visit::visit_item_fn(v, item));
+    }
+
+    fn visit_impl_item_method(&mut self, item: &'ast syn::ImplItemMethod) {
+        self.visit_scoped(false, |v| syn::visit::visit_impl_item_method(v, item));
+    }
+
+    // index fns, like `|o| Addr::unchecked("zero")`, do not build accessors
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if self.any_call || is_accessor_constructor(&call.func) {
+            self.declare_last(call.args.iter());
+        }
+        syn::visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if self.any_call {
+            self.declare_last(call.args.iter());
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+}
+
+/// Items of `storage_namespaces!`.
+struct NamespaceItems(Vec<syn::Item>);
+
+impl Parse for NamespaceItems {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut items = vec![];
+        while !input.is_empty() {
+            items.push(input.parse()?);
+        }
+        Ok(NamespaceItems(items))
+    }
+}
+
+fn item_attrs(item: &mut syn::Item) -> Option<&mut Vec<syn::Attribute>> {
+    match item {
+        syn::Item::Const(item) => Some(&mut item.attrs),
+        syn::Item::Static(item) => Some(&mut item.attrs),
+        syn::Item::Fn(item) => Some(&mut item.attrs),
+        syn::Item::Impl(item) => Some(&mut item.attrs),
+        syn::Item::Mod(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn expand_storage_namespaces(mut items: Vec<syn::Item>) -> TokenStream2 {
+    let mut visitor = NamespaceVisitor::default();
+
+    for item in &mut items {
+        if let Some(attrs) = item_attrs(item) {
+            for attr in attrs.iter().filter(|a| a.path.is_ident("storage_ns")) {
+                match attr.parse_args_with(Punctuated::<syn::LitStr, Token![,]>::parse_terminated) {
+                    Ok(parts) => visitor
+                        .declared
+                        .extend(parts.iter().map(|ns| (ns.value(), ns.span()))),
+                    Err(err) => visitor.push_error(err),
+                }
+            }
+            attrs.retain(|a| !a.path.is_ident("storage_ns"));
+        }
+        syn::visit::visit_item(&mut visitor, item);
+    }
 
-            TokenStream::from(expanded)
+    let mut seen = HashMap::new();
+    for (ns, span) in std::mem::take(&mut visitor.declared) {
+        match seen.get(&ns) {
+            Some(&first) => {
+                let mut err =
+                    syn::Error::new(span, format!("storage namespace `{}` is already used", ns));
+                err.combine(syn::Error::new(
+                    first,
+                    format!("`{}` is first used here", ns),
+                ));
+                visitor.push_error(err);
+            }
+            None => {
+                seen.insert(ns, span);
+            }
         }
-        _ => panic!("#[derive(StorageKey)] currently only support enums."),
     }
+
+    // keep the items on errors, so only the namespaces are reported
+    let errors = visitor.error.map(syn::Error::into_compile_error);
+    quote! {
+        #errors
+        #(#items)*
+    }
+}
+
+#[proc_macro]
+pub fn storage_namespaces(input: TokenStream) -> TokenStream {
+    let NamespaceItems(items) = parse_macro_input!(input as NamespaceItems);
+
+    expand_storage_namespaces(items).into()
 }
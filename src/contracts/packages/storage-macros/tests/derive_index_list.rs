@@ -0,0 +1,136 @@
+use cosmwasm_std::{testing::MockStorage, Addr, Order, StdResult};
+use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex, UniqueIndex};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tw_storage_macros::IndexList;
+
+mod state {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct Order {
+        pub id: u64,
+        pub owner: Addr,
+    }
+}
+
+#[derive(IndexList)]
+struct OrderIndexes<'a> {
+    owner: cw_storage_plus::MultiIndex<'a, Addr, crate::state::Order, u64>,
+    id: UniqueIndex<'a, u64, state::Order>,
+    #[index(skip)]
+    label: &'a str,
+}
+
+#[derive(IndexList)]
+struct GenericIndexes<'a, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    owner: MultiIndex<'a, Addr, T, u64>,
+}
+
+struct OwnerIndex<'a>(MultiIndex<'a, Addr, state::Order, u64>);
+
+impl Index<state::Order> for OwnerIndex<'_> {
+    fn save(
+        &self,
+        store: &mut dyn cosmwasm_std::Storage,
+        pk: &[u8],
+        data: &state::Order,
+    ) -> StdResult<()> {
+        self.0.save(store, pk, data)
+    }
+
+    fn remove(
+        &self,
+        store: &mut dyn cosmwasm_std::Storage,
+        pk: &[u8],
+        old_data: &state::Order,
+    ) -> StdResult<()> {
+        self.0.remove(store, pk, old_data)
+    }
+}
+
+#[derive(IndexList)]
+#[index_list(state::Order)]
+struct CustomIndexes<'a>(OwnerIndex<'a>);
+
+fn owners<I: IndexList<state::Order>>(
+    map: &IndexedMap<u64, state::Order, I>,
+    storage: &MockStorage,
+) -> Vec<u64> {
+    map.range(storage, None, None, Order::Ascending)
+        .map(|r| r.map(|(k, _)| k))
+        .collect::<StdResult<_>>()
+        .unwrap()
+}
+
+#[test]
+fn infers_value_type() {
+    let mut storage = MockStorage::new();
+    let map = IndexedMap::new(
+        "o",
+        OrderIndexes {
+            owner: MultiIndex::new(|o: &state::Order| o.owner.clone(), "o", "o__owner"),
+            id: UniqueIndex::new(|o| o.id, "o__id"),
+            label: "orders",
+        },
+    );
+    assert_eq!(map.idx.label, "orders");
+
+    for id in [1, 2] {
+        let order = state::Order {
+            id,
+            owner: Addr::unchecked("a"),
+        };
+        map.save(&mut storage, id, &order).unwrap();
+    }
+    assert_eq!(owners(&map, &storage), vec![1, 2]);
+    assert_eq!(
+        map.idx
+            .owner
+            .prefix(Addr::unchecked("a"))
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![1, 2]
+    );
+    assert!(map.idx.id.item(&storage, 2).unwrap().is_some());
+}
+
+#[test]
+fn generics_and_explicit_value_type() {
+    let mut storage = MockStorage::new();
+    let generic = IndexedMap::new(
+        "g",
+        GenericIndexes {
+            owner: MultiIndex::new(|o: &state::Order| o.owner.clone(), "g", "g__owner"),
+        },
+    );
+    let custom: IndexedMap<u64, _, _> = IndexedMap::new(
+        "c",
+        CustomIndexes(OwnerIndex(MultiIndex::new(
+            |o: &state::Order| o.owner.clone(),
+            "c",
+            "c__owner",
+        ))),
+    );
+
+    let order = state::Order {
+        id: 7,
+        owner: Addr::unchecked("b"),
+    };
+    generic.save(&mut storage, 7, &order).unwrap();
+    custom.save(&mut storage, 7, &order).unwrap();
+
+    assert_eq!(owners(&generic, &storage), vec![7]);
+    let OwnerIndex(owner) = &custom.idx.0;
+    assert_eq!(
+        owner
+            .prefix(Addr::unchecked("b"))
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![7]
+    );
+}
@@ -1,8 +1,9 @@
 use cosmwasm_std::testing::MockStorage;
-use cw_storage_plus::Map;
+use cosmwasm_std::{Addr, Order, StdError, StdResult};
+use cw_storage_plus::{KeyDeserialize, Map, PrimaryKey};
 use tw_storage_macros::StorageKey;
 
-#[derive(Clone, Copy, StorageKey)]
+#[derive(Clone, Copy, Debug, StorageKey)]
 enum TestEnum {
     G,
     F,
@@ -21,3 +22,360 @@ fn compile_and_works() {
     assert_eq!(map.load(&storage, TestEnum::G).unwrap(), 4);
     assert_eq!(map_2.load(&storage, &TestEnum::F).unwrap(), 4);
 }
+
+#[test]
+fn invalid_bytes_err() {
+    assert!(matches!(TestEnum::try_from_slice(&[1]), Ok(TestEnum::F)));
+
+    let err = TestEnum::try_from_slice(&[7]).unwrap_err();
+    assert!(
+        matches!(&err, StdError::ParseErr { target_type, msg } if target_type == "TestEnum" && msg.contains("0x07"))
+    );
+    assert!(matches!(
+        TestEnum::try_from_slice(&[]),
+        Err(StdError::ParseErr { .. })
+    ));
+    assert!(matches!(
+        TestEnum::from_vec(vec![0, 1]),
+        Err(StdError::ParseErr { .. })
+    ));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+#[storage_key(explicit)]
+enum Explicit {
+    A = 3,
+    #[storage_key(id = 1)]
+    B,
+    C = 255,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+enum Mixed {
+    A,
+    B = 5,
+    C,
+    #[storage_key(id = 2)]
+    D,
+}
+
+#[test]
+fn explicit_ids() {
+    assert_eq!(Explicit::A.as_bytes(), [3]);
+    assert_eq!(Explicit::B.as_bytes(), [1]);
+    assert_eq!(Explicit::C.as_bytes(), [255]);
+    assert_eq!(Explicit::try_from_slice(&[1]).unwrap(), Explicit::B);
+    assert!(Explicit::try_from_slice(&[0]).is_err());
+
+    assert_eq!(Mixed::A.as_bytes(), [0]);
+    assert_eq!(Mixed::B.as_bytes(), [5]);
+    assert_eq!(Mixed::C.as_bytes(), [6]);
+    assert_eq!(Mixed::D.as_bytes(), [2]);
+    assert_eq!(Mixed::try_from_slice(&[6]).unwrap(), Mixed::C);
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+enum Asset {
+    Native(String),
+    #[storage_key(id = 5)]
+    Token(Addr),
+    Pair {
+        base: String,
+        id: u64,
+    },
+    Unknown,
+}
+
+#[test]
+fn payload_variants() {
+    let mut storage = MockStorage::new();
+    let map: Map<&Asset, u64> = Map::new("assets");
+
+    let assets = vec![
+        Asset::Native("uluna".to_string()),
+        Asset::Native("uusd".to_string()),
+        Asset::Token(Addr::unchecked("token")),
+        Asset::Pair {
+            base: "uluna".to_string(),
+            id: 7,
+        },
+        Asset::Unknown,
+    ];
+    for (i, asset) in assets.iter().enumerate() {
+        map.save(&mut storage, asset, &(i as u64)).unwrap();
+    }
+    for (i, asset) in assets.iter().enumerate() {
+        assert_eq!(map.load(&storage, asset).unwrap(), i as u64);
+        assert_eq!(Asset::from_vec(asset.joined_key()).unwrap(), *asset);
+    }
+
+    let natives = map
+        .prefix(0)
+        .range(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(natives, vec![(b"uluna".to_vec(), 0), (b"uusd".to_vec(), 1)]);
+    assert_eq!(
+        map.prefix(5)
+            .keys_raw(&storage, None, None, Order::Ascending)
+            .count(),
+        1
+    );
+    assert_eq!(
+        map.prefix(7)
+            .keys_raw(&storage, None, None, Order::Ascending)
+            .count(),
+        0
+    );
+
+    let all = map
+        .range(&storage, None, None, Order::Ascending)
+        .map(|r| r.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(all, assets);
+
+    assert!(Asset::try_from_slice(&[0, 1, 9]).is_err());
+    assert!(Asset::try_from_slice(&[0, 1, 7, 0, 5, b'a']).is_err());
+    assert!(Asset::try_from_slice(&[0, 1, 8, 1]).is_err());
+    assert!(Asset::try_from_slice(&[0, 1, 7]).is_err());
+    assert!(Asset::try_from_slice(&[5]).is_err());
+    assert!(Asset::try_from_slice(&[8]).is_err());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+enum Legacy {
+    #[storage_key(id = 7)]
+    Unknown,
+}
+
+#[test]
+fn payload_enum_unit_bytes() {
+    assert_eq!(Asset::Unknown.joined_key(), vec![7]);
+    assert_eq!(Asset::Unknown.joined_key(), Legacy::Unknown.joined_key());
+    assert_eq!(
+        Asset::Native("ab".to_string()).joined_key(),
+        vec![0, 1, 0, b'a', b'b']
+    );
+    assert_eq!(
+        Asset::Pair {
+            base: "a".to_string(),
+            id: 1,
+        }
+        .joined_key(),
+        vec![0, 1, 6, 0, 1, b'a', 0, 0, 0, 0, 0, 0, 0, 1]
+    );
+    assert_eq!(Asset::try_from_slice(&[7]).unwrap(), Asset::Unknown);
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+struct PoolKey {
+    base: Addr,
+    quote: Addr,
+    fee: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+struct Pair(String, u64);
+
+#[test]
+fn struct_keys() {
+    let mut storage = MockStorage::new();
+    let pools: Map<&PoolKey, u64> = Map::new("pools");
+
+    let pool = |base: &str, quote: &str, fee: u16| PoolKey {
+        base: Addr::unchecked(base),
+        quote: Addr::unchecked(quote),
+        fee,
+    };
+    let keys = [
+        pool("a", "b", 30),
+        pool("a", "b", 5),
+        pool("a", "c", 30),
+        pool("b", "c", 1),
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        pools.save(&mut storage, key, &(i as u64)).unwrap();
+    }
+    assert_eq!(pools.load(&storage, &keys[2]).unwrap(), 2);
+    assert_eq!(PoolKey::from_vec(keys[3].joined_key()).unwrap(), keys[3]);
+
+    let fees = pools
+        .prefix((Addr::unchecked("a"), Addr::unchecked("b")))
+        .range(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(fees, vec![(5, 1), (30, 0)]);
+
+    let quotes = pools
+        .sub_prefix(Addr::unchecked("a"))
+        .keys(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        quotes,
+        vec![
+            (Addr::unchecked("b"), 5),
+            (Addr::unchecked("b"), 30),
+            (Addr::unchecked("c"), 30)
+        ]
+    );
+
+    let all = pools
+        .keys(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(all.len(), 4);
+    assert_eq!(all[0], keys[1]);
+
+    let pairs: Map<Pair, u64> = Map::new("pairs");
+    pairs
+        .save(&mut storage, Pair("uluna".to_string(), 2), &1)
+        .unwrap();
+    let ids = pairs
+        .prefix("uluna".to_string())
+        .keys(&storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(ids, vec![2]);
+    assert!(Pair::try_from_slice(&[0, 9, b'a']).is_err());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+struct OrderId(u64);
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+struct Denom {
+    denom: String,
+}
+
+#[test]
+fn newtype_keys() {
+    let mut storage = MockStorage::new();
+    let orders: Map<OrderId, u64> = Map::new("orders");
+    let denoms: Map<&Denom, u64> = Map::new("denoms");
+    let nested: Map<(&Denom, OrderId), u64> = Map::new("nested");
+
+    orders.save(&mut storage, OrderId(2), &20).unwrap();
+    orders.save(&mut storage, OrderId(1), &10).unwrap();
+    assert_eq!(OrderId(7).joined_key(), 7u64.joined_key());
+    assert_eq!(
+        orders
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![OrderId(1), OrderId(2)]
+    );
+    assert_eq!(
+        OrderId::from_vec(vec![0, 0, 0, 0, 0, 0, 0, 3]).unwrap(),
+        OrderId(3)
+    );
+
+    let denom = Denom {
+        denom: "uluna".to_string(),
+    };
+    denoms.save(&mut storage, &denom, &1).unwrap();
+    assert_eq!(denoms.load(&storage, &denom).unwrap(), 1);
+
+    nested.save(&mut storage, (&denom, OrderId(5)), &3).unwrap();
+    assert_eq!(
+        nested
+            .prefix(&denom)
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(OrderId(5), 3)]
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+#[storage_key(repr = u16)]
+enum Wide {
+    A,
+    B = 300,
+    C,
+}
+
+#[derive(Clone, Debug, PartialEq, StorageKey)]
+#[storage_key(repr = u32)]
+enum WidePayload {
+    #[storage_key(id = 70000)]
+    Named(String),
+    Empty,
+}
+
+#[test]
+fn wide_repr() {
+    assert_eq!(Wide::A.as_bytes(), [0, 0]);
+    assert_eq!(Wide::C.as_bytes(), 301u16.to_be_bytes());
+    assert_eq!(Wide::B.joined_key(), vec![1, 44]);
+    assert_eq!(Wide::try_from_slice(&[1, 45]).unwrap(), Wide::C);
+    assert!(Wide::try_from_slice(&[1]).is_err());
+    assert!(Wide::try_from_slice(&[0, 0, 0]).is_err());
+    let err = Wide::try_from_slice(&[0, 7]).unwrap_err();
+    assert!(matches!(err, StdError::ParseErr { msg, .. } if msg.contains("0x0007")));
+
+    let mut storage = MockStorage::new();
+    let map: Map<&WidePayload, u64> = Map::new("wide");
+    let named = WidePayload::Named("a".to_string());
+    map.save(&mut storage, &named, &1).unwrap();
+    map.save(&mut storage, &WidePayload::Empty, &2).unwrap();
+    assert_eq!(
+        map.prefix(70000)
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![b"a".to_vec()]
+    );
+    assert_eq!(WidePayload::from_vec(named.joined_key()).unwrap(), named);
+    assert_eq!(WidePayload::Empty.joined_key(), 70001u32.to_be_bytes());
+    assert_eq!(
+        WidePayload::from_vec(WidePayload::Empty.joined_key()).unwrap(),
+        WidePayload::Empty
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StorageKey)]
+#[storage_key(string)]
+enum Side {
+    Buy,
+    #[storage_key(rename = "ask")]
+    Sell,
+    Cancel,
+}
+
+#[test]
+fn string_keys() {
+    assert_eq!(Side::Buy.joined_key(), b"Buy".to_vec());
+    assert_eq!(Side::Sell.joined_key(), b"ask".to_vec());
+    assert_eq!(Side::try_from_slice(b"ask").unwrap(), Side::Sell);
+    let err = Side::try_from_slice(b"Sell").unwrap_err();
+    assert!(
+        matches!(err, StdError::ParseErr { target_type, msg } if target_type == "Side" && msg.contains("\"Sell\""))
+    );
+
+    let mut storage = MockStorage::new();
+    let map: Map<Side, u64> = Map::new("sides");
+    for (i, side) in [Side::Sell, Side::Buy, Side::Cancel]
+        .into_iter()
+        .enumerate()
+    {
+        map.save(&mut storage, side, &(i as u64)).unwrap();
+    }
+    assert_eq!(
+        map.keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![Side::Buy, Side::Cancel, Side::Sell]
+    );
+
+    let nested: Map<(Side, u64), u64> = Map::new("nested");
+    nested.save(&mut storage, (Side::Sell, 3), &1).unwrap();
+    assert_eq!(
+        nested
+            .prefix(Side::Sell)
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![3]
+    );
+}
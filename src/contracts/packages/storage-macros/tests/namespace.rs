@@ -0,0 +1,23 @@
+use cosmwasm_std::{testing::MockStorage, Addr};
+use cw_storage_plus::{Item, Map};
+use tw_storage_macros::namespace;
+
+const POOL: &str = namespace!("pool");
+const BALANCES: Map<&Addr, u64> = Map::new(namespace!("pool", "balances"));
+const TOTAL: Item<u64> = Item::new(namespace!("pool", "balances", "total",));
+
+#[test]
+fn joins_namespace() {
+    assert_eq!(POOL, "pool");
+    assert_eq!(namespace!("pool", "balances"), "pool-balances");
+
+    let mut storage = MockStorage::new();
+    let owner = Addr::unchecked("a");
+    BALANCES.save(&mut storage, &owner, &1).unwrap();
+    TOTAL.save(&mut storage, &2).unwrap();
+
+    let balances: Map<&Addr, u64> = Map::new("pool-balances");
+    let total: Item<u64> = Item::new("pool-balances-total");
+    assert_eq!(balances.load(&storage, &owner).unwrap(), 1);
+    assert_eq!(total.load(&storage).unwrap(), 2);
+}
@@ -0,0 +1,20 @@
+use tw_storage_macros::IndexList;
+
+#[derive(IndexList)]
+struct NotAnIndex<'a> {
+    label: &'a str,
+}
+
+#[derive(IndexList)]
+struct Empty {}
+
+#[derive(IndexList)]
+struct BadOption {
+    #[index(unique)]
+    a: u8,
+}
+
+#[derive(IndexList)]
+enum NotAStruct {}
+
+fn main() {}
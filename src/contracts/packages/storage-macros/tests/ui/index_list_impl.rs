@@ -0,0 +1,18 @@
+use tw_storage_macros::index_list_impl;
+
+struct TestStruct;
+
+#[index_list_impl]
+struct MissingType<'a> {
+    a: &'a u8,
+}
+
+#[index_list_impl(TestStruct)]
+struct NoLifetime {
+    a: u8,
+}
+
+#[index_list_impl(TestStruct)]
+struct Tuple<'a>(&'a u8);
+
+fn main() {}
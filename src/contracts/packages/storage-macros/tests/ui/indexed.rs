@@ -0,0 +1,38 @@
+use tw_storage_macros::Indexed;
+
+#[derive(Indexed)]
+struct NoIndex {
+    a: u8,
+}
+
+#[derive(Indexed)]
+struct MissingKind {
+    #[index(key = u64)]
+    a: u8,
+}
+
+#[derive(Indexed)]
+struct UniqueWhen {
+    #[index(unique, when = "cond")]
+    a: u8,
+}
+
+#[derive(Indexed)]
+struct TwoKinds {
+    #[index(unique, multi)]
+    a: u8,
+}
+
+#[derive(Indexed)]
+struct Unknown {
+    #[index(primary)]
+    a: u8,
+}
+
+#[derive(Indexed)]
+struct Generic<T> {
+    #[index(unique)]
+    a: T,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use tw_storage_macros::namespace;
+
+const EMPTY: &str = namespace!();
+const EMPTY_PART: &str = namespace!("pool", "");
+const NOT_A_STRING: &str = namespace!("pool", 1);
+
+fn main() {}
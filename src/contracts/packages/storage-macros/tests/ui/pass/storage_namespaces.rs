@@ -0,0 +1,38 @@
+use cosmwasm_std::{Addr, StdError, StdResult};
+use cw_storage_plus::{Item, Map, MultiIndex};
+use tw_storage_macros::storage_namespaces;
+
+storage_namespaces! {
+    const CONFIG: Item<u64> = Item::new("config");
+    const BALANCES: Map<&Addr, u64> = Map::new("balances");
+    const ADMIN: &str = "admin";
+    const OWNER: &str = "admin";
+
+    fn check_admin(sender: &str) -> StdResult<()> {
+        if sender != ADMIN {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+        Ok(())
+    }
+
+    fn check_owner(sender: &str) -> StdResult<()> {
+        if sender != OWNER {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+        Ok(())
+    }
+
+    fn by_owner<'a>() -> MultiIndex<'a, Addr, u64, u64> {
+        MultiIndex::new(|_| Addr::unchecked("zero"), "orders", "orders__owner")
+    }
+
+    fn by_payer<'a>() -> MultiIndex<'a, Addr, u64, u64> {
+        MultiIndex::new(|_| Addr::unchecked("zero"), "orders", "orders__payer")
+    }
+}
+
+fn main() {
+    check_admin("admin").unwrap();
+    check_owner("admin").unwrap();
+    let _ = (CONFIG, BALANCES, by_owner(), by_payer());
+}
@@ -0,0 +1,44 @@
+use tw_storage_macros::SortKey;
+
+#[derive(SortKey)]
+enum NotStruct {
+    A,
+}
+
+#[derive(SortKey)]
+struct Empty {}
+
+#[derive(SortKey)]
+struct MissingOrder {
+    a: u64,
+}
+
+#[derive(SortKey)]
+struct UnknownArg {
+    #[sort(up)]
+    a: u64,
+}
+
+#[derive(SortKey)]
+struct OrderTwice {
+    #[sort(asc, desc)]
+    a: u64,
+}
+
+#[derive(SortKey)]
+struct TwoPks {
+    #[sort(asc, pk)]
+    a: u64,
+    #[sort(asc, pk)]
+    b: u64,
+}
+
+#[derive(SortKey)]
+struct PkNotLast {
+    #[sort(asc, pk)]
+    a: u64,
+    #[sort(desc)]
+    b: u64,
+}
+
+fn main() {}
@@ -0,0 +1,45 @@
+use tw_storage_macros::StorageKey;
+
+#[derive(StorageKey)]
+#[storage_key(unknown)]
+enum UnknownOption {
+    A,
+}
+
+#[derive(StorageKey)]
+enum NonIntegerId {
+    #[storage_key(id = "1")]
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(id = 1)]
+enum IdOnEnum {
+    A,
+}
+
+#[derive(StorageKey)]
+enum ExplicitOnVariant {
+    #[storage_key(explicit)]
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(repr = u64)]
+enum InvalidRepr {
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(repr = u16)]
+struct ReprOnStruct(u8, u8);
+
+#[derive(StorageKey)]
+enum Empty {}
+
+#[derive(StorageKey)]
+union Union {
+    a: u8,
+}
+
+fn main() {}
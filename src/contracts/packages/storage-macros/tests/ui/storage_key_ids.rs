@@ -0,0 +1,43 @@
+use tw_storage_macros::StorageKey;
+
+#[derive(StorageKey)]
+enum Duplicate {
+    A,
+    #[storage_key(id = 0)]
+    B,
+}
+
+#[derive(StorageKey)]
+enum IdAndDiscriminant {
+    #[storage_key(id = 1)]
+    A = 1,
+}
+
+const ONE: isize = 1;
+
+#[derive(StorageKey)]
+enum NonLiteralDiscriminant {
+    A = ONE,
+}
+
+#[derive(StorageKey)]
+#[storage_key(explicit)]
+enum MissingExplicit {
+    A = 1,
+    B,
+}
+
+#[derive(StorageKey)]
+enum Overflow {
+    A = 255,
+    B,
+}
+
+#[derive(StorageKey)]
+#[storage_key(repr = u16)]
+enum TooLarge {
+    #[storage_key(id = 65536)]
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,48 @@
+use tw_storage_macros::StorageKey;
+
+#[derive(StorageKey)]
+#[storage_key(string, repr = u16)]
+enum WithRepr {
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(string, explicit)]
+enum WithExplicit {
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(string)]
+enum Payload {
+    A(u8),
+}
+
+#[derive(StorageKey)]
+#[storage_key(string)]
+enum WithId {
+    #[storage_key(id = 1)]
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(string)]
+enum DuplicateName {
+    A,
+    #[storage_key(rename = "A")]
+    B,
+}
+
+#[derive(StorageKey)]
+enum RenameWithoutString {
+    #[storage_key(rename = "a")]
+    A,
+}
+
+#[derive(StorageKey)]
+#[storage_key(rename = "a")]
+enum RenameOnType {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use tw_storage_macros::StorageKey;
+
+#[derive(StorageKey)]
+struct Unit;
+
+#[derive(StorageKey)]
+struct TooManyFields(u8, u8, u8, u8, u8);
+
+#[derive(Clone, StorageKey)]
+struct MultiSegmentField {
+    pair: (u8, u8),
+    id: u64,
+}
+
+#[derive(Clone, StorageKey)]
+enum MultiSegmentVariant {
+    Pair((u8, u8)),
+    Unit,
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use tw_storage_macros::storage_layout;
+
+struct Item<'a>(&'a str);
+
+#[storage_layout(namespace = "a")]
+struct UnknownOption<'a> {
+    a: Item<'a>,
+}
+
+#[storage_layout(prefix = 1)]
+struct NotAString<'a> {
+    a: Item<'a>,
+}
+
+#[storage_layout]
+struct Tuple<'a>(Item<'a>);
+
+#[storage_layout]
+struct UnknownField<'a> {
+    #[layout(rename = "b")]
+    a: Item<'a>,
+}
+
+fn build(ns: &str) -> Item<'static> {
+    Item(Box::leak(ns.to_string().into_boxed_str()))
+}
+
+#[storage_layout(prefix = "a")]
+struct ConstWith<'a> {
+    #[layout(with = "build")]
+    a: Item<'a>,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Item, Map, MultiIndex};
+use tw_storage_macros::{namespace, storage_namespaces};
+
+storage_namespaces! {
+    const CONFIG: Item<u64> = Item::new("config");
+    const STATE: Item<u64> = Item::new("config");
+    const BALANCES: Map<&Addr, u64> = Map::new("pool-balances");
+    const POOL_BALANCES: Map<&Addr, u64> = Map::new(namespace!("pool", "balances"));
+
+    // an index stored under its pk namespace
+    fn by_owner<'a>() -> MultiIndex<'a, Addr, u64, u64> {
+        MultiIndex::new(|_| Addr::unchecked(""), "orders", "orders")
+    }
+
+    #[storage_ns("config", 1)]
+    fn not_a_string() {}
+}
+
+fn main() {}